@@ -0,0 +1,97 @@
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use sage_mqtt::{
+    BinaryData, Connect, Packet, Publish, QoS, Subscribe, SubscriptionOptions, Topic, Will,
+};
+use std::io::Cursor;
+use tokio::runtime::Builder;
+
+/// Benchmarks both encoding and decoding of `packet` within a group named
+/// `name`. Throughput is expressed in encoded bytes.
+fn bench_packet(c: &mut Criterion, name: &str, packet: Packet) {
+    let runtime = Builder::new_current_thread().build().unwrap();
+    let encoded = runtime.block_on(packet.clone().encode_to_vec()).unwrap();
+
+    let mut group = c.benchmark_group(name);
+    group.throughput(Throughput::Bytes(encoded.len() as u64));
+    group.bench_function("encode", |b| {
+        b.iter_batched(
+            || packet.clone(),
+            |packet| runtime.block_on(packet.encode_to_vec()).unwrap(),
+            BatchSize::SmallInput,
+        )
+    });
+    group.bench_function("decode", |b| {
+        b.iter(|| {
+            runtime
+                .block_on(Packet::decode(Cursor::new(black_box(&encoded))))
+                .unwrap()
+        })
+    });
+    group.finish();
+}
+
+fn small_publish(c: &mut Criterion) {
+    let packet = Publish {
+        topic_name: Topic::from("sensors/kitchen/temperature"),
+        message: b"21.5".to_vec(),
+        ..Default::default()
+    };
+    bench_packet(c, "small_publish", packet.into());
+}
+
+fn large_publish(c: &mut Criterion) {
+    let packet = Publish {
+        qos: QoS::AtLeastOnce,
+        packet_identifier: Some(1337),
+        topic_name: Topic::from("firmware/update"),
+        content_type: "application/octet-stream".into(),
+        message: vec![0x2A; 256 * 1024],
+        ..Default::default()
+    };
+    bench_packet(c, "large_publish", packet.into());
+}
+
+fn connect_with_will(c: &mut Criterion) {
+    let packet = Connect {
+        clean_start: true,
+        client_id: Some("benchclient".into()),
+        user_name: Some("Willow".into()),
+        password: Some(BinaryData::try_new("Jaden").unwrap()),
+        will: Some(Will {
+            qos: QoS::AtLeastOnce,
+            delay_interval: 10,
+            user_properties: vec![("Mogwaï".into(), "Cat".into())],
+            ..Will::with_message(Topic::from("clients/benchclient/status"), "offline")
+        }),
+        ..Default::default()
+    };
+    bench_packet(c, "connect_with_will", packet.into());
+}
+
+fn subscribe_50_filters(c: &mut Criterion) {
+    let packet = Subscribe {
+        packet_identifier: 1337,
+        subscriptions: (0..50)
+            .map(|i| {
+                (
+                    Topic::from(format!("building/{}/+/temperature", i)),
+                    SubscriptionOptions {
+                        qos: QoS::AtLeastOnce,
+                        ..Default::default()
+                    },
+                )
+            })
+            .collect(),
+        ..Default::default()
+    };
+    bench_packet(c, "subscribe_50_filters", packet.into());
+}
+
+criterion_group!(
+    benches,
+    small_publish,
+    large_publish,
+    connect_with_will,
+    subscribe_50_filters
+);
+criterion_main!(benches);
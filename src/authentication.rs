@@ -1,4 +1,4 @@
-use crate::{Property, Result as SageResult};
+use crate::{codec, BinaryData, Error, Property, ReasonCode::ProtocolError, Result as SageResult};
 use std::marker::Unpin;
 use tokio::io::AsyncWrite;
 
@@ -25,23 +25,68 @@ pub struct Authentication {
 
     /// Authentication may contains data. The content depends on the
     /// authentication method.
-    pub data: Vec<u8>,
+    pub data: BinaryData,
 }
 
 impl Authentication {
-    ///Write authentication data into `writer`, returning the written size
-    /// in case of success.
+    /// Writes the authentication as properties into `writer`, returning the
+    /// written size in case of success. `writer` is meant to be the property
+    /// buffer of a `Connect` or `Auth` packet: the `AuthenticationMethod`
+    /// property is always written, followed by the `AuthenticationData`
+    /// property if `data` is not empty. The length of the property block is
+    /// not written.
     pub async fn write<W: AsyncWrite + Unpin>(self, writer: &mut W) -> SageResult<usize> {
         let mut n_bytes = Property::AuthenticationMethod(self.method)
             .encode(writer)
             .await?;
         if !self.data.is_empty() {
-            n_bytes += Property::AuthenticationData(self.data)
+            n_bytes += Property::AuthenticationData(self.data.into())
                 .encode(writer)
                 .await?;
         }
         Ok(n_bytes)
     }
+
+    /// Reads an authentication out of `properties`, a property buffer such as
+    /// the one produced by `write`, without the length of the property
+    /// block. Fails with `ProtocolError` if the buffer contains any other
+    /// property or lacks the `AuthenticationMethod` property.
+    pub async fn read(properties: &[u8]) -> SageResult<Self> {
+        let mut block = Vec::new();
+        codec::write_variable_byte_integer(properties.len() as u32, &mut block).await?;
+        block.extend_from_slice(properties);
+
+        let mut method = None;
+        let mut data = Default::default();
+        for property in Property::decode_block(&block[..]).await? {
+            match property {
+                Property::AuthenticationMethod(v) => method = Some(v),
+                Property::AuthenticationData(v) => data = BinaryData::try_new(v)?,
+                p => {
+                    return Err(Error::Context(
+                        ProtocolError,
+                        format!("{:?} property is not an authentication property", p.id()),
+                    ))
+                }
+            }
+        }
+        match method {
+            Some(method) => Ok(Authentication { method, data }),
+            None => Err(Error::Context(
+                ProtocolError,
+                "missing authentication method".into(),
+            )),
+        }
+    }
+
+    /// The number of bytes `write` would produce.
+    pub fn encoded_len(&self) -> usize {
+        let mut n_bytes = 3 + self.method.len();
+        if !self.data.is_empty() {
+            n_bytes += 3 + self.data.len();
+        }
+        n_bytes
+    }
 }
 
 #[cfg(test)]
@@ -63,7 +108,7 @@ mod unit {
         let mut result = Vec::new();
         let test_data = Authentication {
             method: "Willow".into(),
-            data: vec![0x0D, 0x15, 0xEA, 0x5E],
+            data: BinaryData::try_new(vec![0x0D, 0x15, 0xEA, 0x5E]).unwrap(),
         };
 
         assert_eq!(test_data.write(&mut result).await.unwrap(), 16);
@@ -72,4 +117,31 @@ mod unit {
             vec![21, 0, 6, 87, 105, 108, 108, 111, 119, 22, 0, 4, 13, 21, 234, 94]
         );
     }
+
+    #[tokio::test]
+    async fn round_trip() {
+        let test_data = Authentication {
+            method: "Willow".into(),
+            data: BinaryData::try_new(vec![0x0D, 0x15, 0xEA, 0x5E]).unwrap(),
+        };
+        let mut properties = Vec::new();
+        let n_bytes = test_data.clone().write(&mut properties).await.unwrap();
+        assert_eq!(test_data.encoded_len(), n_bytes);
+        assert_eq!(Authentication::read(&properties).await.unwrap(), test_data);
+
+        let test_data = Authentication::default();
+        let mut properties = Vec::new();
+        let n_bytes = test_data.clone().write(&mut properties).await.unwrap();
+        assert_eq!(test_data.encoded_len(), n_bytes);
+        assert_eq!(Authentication::read(&properties).await.unwrap(), test_data);
+    }
+
+    #[tokio::test]
+    async fn read_without_method() {
+        // A lone AuthenticationData property
+        assert!(matches!(
+            Authentication::read(&[0x16, 0x00, 0x01, 0x2A]).await,
+            Err(Error::Context(ProtocolError, _))
+        ));
+    }
 }
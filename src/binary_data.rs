@@ -0,0 +1,92 @@
+use crate::{Error, ReasonCode::MalformedPacket, Result as SageResult};
+use std::{convert::TryFrom, ops::Deref};
+
+/// Arbitrary bytes encoded as MQTT Binary Data, such as correlation data,
+/// passwords or authentication data.
+/// Binary data is prefixed with its size in a two bytes integer on the wire,
+/// therefore it cannot exceed 65535 bytes. This limit is checked upon
+/// construction so that a `BinaryData` can always be encoded.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct BinaryData(Vec<u8>);
+
+impl BinaryData {
+    /// The maximum size in bytes of binary data.
+    pub const MAX_LEN: usize = u16::MAX as usize;
+
+    /// Builds binary data from the given bytes, failing with `MalformedPacket`
+    /// if they exceed `MAX_LEN` bytes.
+    pub fn try_new(data: impl Into<Vec<u8>>) -> SageResult<Self> {
+        let data = data.into();
+        if data.len() > BinaryData::MAX_LEN {
+            Err(Error::Context(
+                MalformedPacket,
+                format!(
+                    "binary data must not exceed {} bytes, got {}",
+                    BinaryData::MAX_LEN,
+                    data.len()
+                ),
+            ))
+        } else {
+            Ok(BinaryData(data))
+        }
+    }
+
+    /// Consumes the binary data, returning the underlying bytes.
+    pub fn into_inner(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+impl TryFrom<Vec<u8>> for BinaryData {
+    type Error = Error;
+
+    fn try_from(data: Vec<u8>) -> SageResult<Self> {
+        BinaryData::try_new(data)
+    }
+}
+
+impl From<BinaryData> for Vec<u8> {
+    fn from(data: BinaryData) -> Self {
+        data.0
+    }
+}
+
+impl Deref for BinaryData {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl AsRef<[u8]> for BinaryData {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod unit {
+
+    use super::*;
+
+    #[test]
+    fn try_new() {
+        let data = BinaryData::try_new(vec![0x0D, 0x15, 0xEA, 0x5E]).unwrap();
+        assert_eq!(&data[..], &[0x0D, 0x15, 0xEA, 0x5E]);
+    }
+
+    #[test]
+    fn try_new_max_len() {
+        let data = BinaryData::try_new(vec![0x2A; BinaryData::MAX_LEN]).unwrap();
+        assert_eq!(data.len(), BinaryData::MAX_LEN);
+    }
+
+    #[test]
+    fn try_new_too_long() {
+        assert!(matches!(
+            BinaryData::try_new(vec![0x2A; BinaryData::MAX_LEN + 1]),
+            Err(Error::Context(MalformedPacket, _))
+        ));
+    }
+}
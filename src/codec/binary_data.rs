@@ -11,7 +11,7 @@ pub async fn write_binary_data<W: AsyncWrite + Unpin>(
     writer: &mut W,
 ) -> SageResult<usize> {
     let len = data.len();
-    if len > i16::max_value() as usize {
+    if len > u16::MAX as usize {
         return Err(IOError::new(ErrorKind::InvalidData, "ERROR_MSG_DATA_TOO_LONG").into());
     }
     writer.write_all(&(len as u16).to_be_bytes()).await?;
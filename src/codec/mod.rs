@@ -13,7 +13,10 @@ pub use byte::{read_bool, read_byte, write_bool, write_byte};
 pub use four_byte_integer::{read_four_byte_integer, write_four_byte_integer};
 pub use packet_type::{read_control_packet_type, write_control_packet_type};
 pub use qos::{read_qos, write_qos};
-pub use reason_code::write_reason_code;
+pub use reason_code::{read_reason_code, write_reason_code};
 pub use two_byte_integer::{read_two_byte_integer, write_two_byte_integer};
 pub use utf8_string::{read_utf8_string, write_utf8_string};
-pub use variable_byte_integer::{read_variable_byte_integer, write_variable_byte_integer};
+pub use variable_byte_integer::{
+    read_variable_byte_integer, write_empty_properties, write_variable_byte_integer,
+    VariableByteInteger,
+};
@@ -18,7 +18,7 @@ pub async fn write_control_packet_type<W: AsyncWrite + Unpin>(
                 duplicate,
                 qos,
                 retain,
-            } => 0b0011_0000 | (duplicate as u8) << 3 | (qos as u8) << 2 | retain as u8,
+            } => 0b0011_0000 | (duplicate as u8) << 3 | (qos as u8) << 1 | retain as u8,
             PacketType::PubAck => 0b0100_0000,
             PacketType::PubRec => 0b0101_0000,
             PacketType::PubRel => 0b0110_0010,
@@ -48,7 +48,7 @@ pub async fn read_control_packet_type<R: AsyncRead + Unpin>(
         (0b0001, 0b0000) => PacketType::Connect,
         (0b0010, 0b0000) => PacketType::ConnAck,
         (0b0011, flags) => PacketType::Publish {
-            duplicate: (flags & 0b0111) > 0,
+            duplicate: (flags & 0b1000) > 0,
             qos: ((flags & 0b0110) >> 1).try_into()?,
             retain: (flags & 0b0001) > 0,
         },
@@ -1,6 +1,6 @@
-use crate::{codec, ReasonCode, Result as SageResult};
+use crate::{codec, PacketType, ReasonCode, Result as SageResult};
 use std::marker::Unpin;
-use tokio::io::AsyncWrite;
+use tokio::io::{AsyncRead, AsyncWrite};
 
 ///Write the given `ReasonCode`in one byte, returning `1` in case of success.
 pub async fn write_reason_code<W: AsyncWrite + Unpin>(
@@ -58,10 +58,51 @@ pub async fn write_reason_code<W: AsyncWrite + Unpin>(
     .await
 }
 
+/// Read the given `reader` for a `ReasonCode` carried by a packet of type
+/// `packet_type`. Fails with `ProtocolError` if the reason code is unknown or
+/// cannot be used in such a packet.
+pub async fn read_reason_code<R: AsyncRead + Unpin>(
+    reader: R,
+    packet_type: PacketType,
+) -> SageResult<ReasonCode> {
+    ReasonCode::try_parse(codec::read_byte(reader).await?, packet_type)
+}
+
 #[cfg(test)]
 mod unit {
 
     use super::*;
+    use crate::Error;
+    use std::io::Cursor;
+
+    #[tokio::test]
+    async fn decode_connack() {
+        let mut test_stream = Cursor::new([0x8A]);
+        assert_eq!(
+            read_reason_code(&mut test_stream, PacketType::ConnAck)
+                .await
+                .unwrap(),
+            ReasonCode::Banned
+        );
+    }
+
+    #[tokio::test]
+    async fn decode_connack_invalid() {
+        let mut test_stream = Cursor::new([0x01]);
+        assert!(matches!(
+            read_reason_code(&mut test_stream, PacketType::ConnAck).await,
+            Err(Error::Reason(ReasonCode::ProtocolError))
+        ));
+    }
+
+    #[tokio::test]
+    async fn decode_unknown() {
+        let mut test_stream = Cursor::new([0x03]);
+        assert!(matches!(
+            read_reason_code(&mut test_stream, PacketType::ConnAck).await,
+            Err(Error::Reason(ReasonCode::ProtocolError))
+        ));
+    }
 
     #[tokio::test]
     async fn encode() {
@@ -1,14 +1,59 @@
-use crate::{ReasonCode::MalformedPacket, Result as SageResult};
+use crate::{Error, ReasonCode::MalformedPacket, Result as SageResult};
 use std::marker::Unpin;
 use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 
+/// A `u32` value which can be encoded as a Variable Byte Integer, that is
+/// which does not exceed `VariableByteInteger::MAX`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct VariableByteInteger(u32);
+
+impl VariableByteInteger {
+    /// The maximum value a Variable Byte Integer can represent.
+    pub const MAX: u32 = 268_435_455;
+
+    /// Checks the given value can be encoded as a Variable Byte Integer,
+    /// failing with `MalformedPacket` otherwise.
+    pub fn try_new(value: u32) -> SageResult<Self> {
+        if value > VariableByteInteger::MAX {
+            Err(Error::Context(
+                MalformedPacket,
+                format!(
+                    "variable byte integer must not exceed {}, got {}",
+                    VariableByteInteger::MAX,
+                    value
+                ),
+            ))
+        } else {
+            Ok(VariableByteInteger(value))
+        }
+    }
+
+    /// The value of the integer.
+    pub fn get(self) -> u32 {
+        self.0
+    }
+
+    /// The number of bytes the integer occupies once encoded: `1`, `2`, `3`
+    /// or `4`.
+    pub fn encoded_len(self) -> usize {
+        match self.0 {
+            0..=127 => 1,
+            128..=16_383 => 2,
+            16_384..=2_097_151 => 3,
+            _ => 4,
+        }
+    }
+}
+
 ///Write the given `u32` into `writer` according to MQTT5 Variable Byte Integer
 /// specifications, returning the number of bytes written (`1`, `2`, `3` or `4`)
 /// in case of success.
+/// Fails with `MalformedPacket` if `data` exceeds `VariableByteInteger::MAX`.
 pub async fn write_variable_byte_integer<W: AsyncWrite + Unpin>(
     data: u32,
     writer: &mut W,
 ) -> SageResult<usize> {
+    VariableByteInteger::try_new(data)?;
     let mut n_encoded_bytes = 0;
     let mut x = data;
     loop {
@@ -25,6 +70,15 @@ pub async fn write_variable_byte_integer<W: AsyncWrite + Unpin>(
     Ok(n_encoded_bytes)
 }
 
+/// Write the length of an empty property block, which is a single `0x00`
+/// byte, returning `1` in case of success.
+/// This is meant for writers emitting a property block known to be empty.
+/// Packets of this crate encode their properties into a buffer first and
+/// write the length of that buffer, whether it is empty or not.
+pub async fn write_empty_properties<W: AsyncWrite + Unpin>(writer: &mut W) -> SageResult<usize> {
+    write_variable_byte_integer(0, writer).await
+}
+
 ///Read the given stream for a `u32` encoded as Variable Byte Integer.
 /// Returns the read value in case of success.
 pub async fn read_variable_byte_integer<R: AsyncRead + Unpin>(reader: &mut R) -> SageResult<u32> {
@@ -35,10 +89,10 @@ pub async fn read_variable_byte_integer<R: AsyncRead + Unpin>(reader: &mut R) ->
         let mut buffer = vec![0u8; 1];
         reader.read_exact(&mut buffer).await?;
         let encoded_byte = buffer[0];
-        value += ((encoded_byte & 127u8) as u32) * multiplier;
         if multiplier > 2_097_152 {
             return Err(MalformedPacket.into());
         }
+        value += ((encoded_byte & 127u8) as u32) * multiplier;
         multiplier *= 128;
         if encoded_byte & 128u8 == 0 {
             break;
@@ -88,6 +142,13 @@ mod unit {
         }
     }
 
+    #[tokio::test]
+    async fn encode_empty_properties() {
+        let mut result = Vec::new();
+        assert_eq!(write_empty_properties(&mut result).await.unwrap(), 1);
+        assert_eq!(result, vec![0x00]);
+    }
+
     #[tokio::test]
     async fn encode_one_lower_bound() {
         let mut result = Vec::new();
@@ -184,6 +245,47 @@ mod unit {
         assert_eq!(result, vec![0xFF, 0xFF, 0xFF, 0x7F]);
     }
 
+    #[tokio::test]
+    async fn encode_too_large() {
+        let mut result = Vec::new();
+        assert!(matches!(
+            write_variable_byte_integer(268_435_456u32, &mut result).await,
+            Err(Error::Context(MalformedPacket, _))
+        ));
+        assert!(result.is_empty());
+    }
+
+    #[test]
+    fn try_new() {
+        assert_eq!(
+            VariableByteInteger::try_new(268_435_455).unwrap().get(),
+            VariableByteInteger::MAX
+        );
+        assert!(matches!(
+            VariableByteInteger::try_new(268_435_456),
+            Err(Error::Context(MalformedPacket, _))
+        ));
+    }
+
+    #[test]
+    fn encoded_len() {
+        for (value, len) in &[
+            (0, 1),
+            (127, 1),
+            (128, 2),
+            (16_383, 2),
+            (16_384, 3),
+            (2_097_151, 3),
+            (2_097_152, 4),
+            (VariableByteInteger::MAX, 4),
+        ] {
+            assert_eq!(
+                VariableByteInteger::try_new(*value).unwrap().encoded_len(),
+                *len
+            );
+        }
+    }
+
     #[tokio::test]
     async fn decode_one_lower_bound() {
         let mut test_stream = Cursor::new([0x00]);
@@ -266,4 +368,13 @@ mod unit {
             panic!("Should be IO Error");
         }
     }
+
+    #[tokio::test]
+    async fn decode_five_bytes() {
+        let mut test_stream = Cursor::new([0xFF, 0xFF, 0xFF, 0xFF, 0x7F]);
+        assert!(matches!(
+            read_variable_byte_integer(&mut test_stream).await,
+            Err(Error::Reason(MalformedPacket))
+        ));
+    }
 }
@@ -1,9 +1,9 @@
 use crate::{
-    codec, Authentication, PropertiesDecoder, Property,
+    codec, Authentication, BinaryData, DecodeOptions, PacketType, PropertiesDecoder, Property,
     ReasonCode::{self, ProtocolError},
     Result as SageResult,
 };
-use std::{convert::TryFrom, marker::Unpin};
+use std::marker::Unpin;
 use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
 
 /// The `Auth` packet is used for enhanced authentication upon connection.
@@ -60,40 +60,55 @@ impl Auth {
         Ok(n_bytes)
     }
 
-    pub(crate) async fn read<R: AsyncRead + Unpin>(mut reader: R) -> SageResult<Self> {
-        let reason_code = ReasonCode::try_from(codec::read_byte(&mut reader).await?)?;
+    pub(crate) async fn read<R: AsyncRead + Unpin>(
+        mut reader: R,
+        remaining_size: usize,
+        options: &DecodeOptions,
+    ) -> SageResult<Self> {
+        // The reason code and properties can be omitted for a successful
+        // authentication
+        if remaining_size == 0 {
+            return Ok(Default::default());
+        }
+
+        let reason_code = codec::read_reason_code(&mut reader, PacketType::Auth).await?;
 
         let mut user_properties = Vec::new();
-        let mut properties = PropertiesDecoder::take(&mut reader).await?;
         let mut reason_string = None;
         let mut authentication_method = None;
         let mut authentication_data = Default::default();
 
-        while properties.has_properties() {
-            match properties.read().await? {
-                Property::ReasonString(v) => reason_string = Some(v),
-                Property::UserProperty(k, v) => user_properties.push((k, v)),
-                Property::AuthenticationMethod(v) => authentication_method = Some(v),
-                Property::AuthenticationData(v) => authentication_data = v,
-                _ => return Err(ProtocolError.into()),
+        // The properties can be omitted as well, leaving the reason code only
+        if remaining_size > 1 {
+            let mut properties = PropertiesDecoder::take(&mut reader, options).await?;
+            while properties.has_properties() {
+                match properties.read().await? {
+                    Property::ReasonString(v) => reason_string = Some(v),
+                    Property::UserProperty(k, v) => user_properties.push((k, v)),
+                    Property::AuthenticationMethod(v) => authentication_method = Some(v),
+                    Property::AuthenticationData(v) => authentication_data = v,
+                    _ => return Err(ProtocolError.into()),
+                }
             }
         }
 
-        if let Some(method) = authentication_method {
-            let authentication = Authentication {
+        let authentication = match authentication_method {
+            Some(method) => Authentication {
                 method,
-                data: authentication_data,
-            };
-
-            Ok(Auth {
-                reason_code,
-                reason_string,
-                authentication,
-                user_properties,
-            })
-        } else {
-            Err(ProtocolError.into())
-        }
+                data: BinaryData::try_new(authentication_data)?,
+            },
+            // Reason code only form of a successful authentication
+            None if reason_code == ReasonCode::Success && remaining_size == 1 => Default::default(),
+            // An authentication exchange cannot go on without a method
+            None => return Err(ProtocolError.into()),
+        };
+
+        Ok(Auth {
+            reason_code,
+            reason_string,
+            authentication,
+            user_properties,
+        })
     }
 }
 
@@ -101,6 +116,7 @@ impl Auth {
 mod unit {
 
     use super::*;
+    use crate::Error;
     use std::io::Cursor;
 
     fn encoded() -> Vec<u8> {
@@ -115,7 +131,7 @@ mod unit {
             reason_code: ReasonCode::ContinueAuthentication,
             authentication: Authentication {
                 method: "Willow".into(),
-                data: vec![0x0D, 0x15, 0xEA, 0x5E],
+                data: BinaryData::try_new(vec![0x0D, 0x15, 0xEA, 0x5E]).unwrap(),
             },
             reason_string: Some("Biwi".into()),
             user_properties: vec![("Mogwaï".into(), "Cat".into())],
@@ -134,7 +150,36 @@ mod unit {
     #[tokio::test]
     async fn decode() {
         let mut test_data = Cursor::new(encoded());
-        let tested_result = Auth::read(&mut test_data).await.unwrap();
+        let tested_result = Auth::read(&mut test_data, 40, &Default::default())
+            .await
+            .unwrap();
         assert_eq!(tested_result, decoded());
     }
+
+    #[tokio::test]
+    async fn decode_empty() {
+        let mut test_data = Cursor::new(Vec::new());
+        let tested_result = Auth::read(&mut test_data, 0, &Default::default())
+            .await
+            .unwrap();
+        assert_eq!(tested_result, Auth::default());
+    }
+
+    #[tokio::test]
+    async fn decode_continue_without_method() {
+        let mut test_data = Cursor::new(vec![0x18, 0]);
+        assert!(matches!(
+            Auth::read(&mut test_data, 2, &Default::default()).await,
+            Err(Error::Reason(ProtocolError))
+        ));
+    }
+
+    #[tokio::test]
+    async fn decode_continue_reason_code_only() {
+        let mut test_data = Cursor::new(vec![0x18]);
+        assert!(matches!(
+            Auth::read(&mut test_data, 1, &Default::default()).await,
+            Err(Error::Reason(ProtocolError))
+        ));
+    }
 }
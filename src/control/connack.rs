@@ -5,12 +5,13 @@ use crate::{
         DEFAULT_SHARED_SUBSCRIPTION_AVAILABLE, DEFAULT_SUBSCRIPTION_IDENTIFIER_AVAILABLE,
         DEFAULT_TOPIC_ALIAS_MAXIMUM, DEFAULT_WILCARD_SUBSCRIPTION_AVAILABLE,
     },
-    Authentication, ClientID, PropertiesDecoder, Property, QoS,
+    Authentication, BinaryData, ClientID, Connect, DecodeOptions, Error, PacketType,
+    PropertiesDecoder, Property, QoS,
     ReasonCode::{self, ProtocolError},
     Result as SageResult,
 };
-use std::{convert::TryInto, marker::Unpin};
-use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
+use std::marker::Unpin;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 
 /// The `Connack` message is sent from the server to the client to acknowledge
 /// the connection request. This can be the direct response to a `Connect`
@@ -89,6 +90,7 @@ pub struct ConnAck {
     /// in `response_information`.
     /// The response information can be used by the client as an hint to
     /// generate reponse topic when making Request/Reponse messages.
+    /// A client which did not ask for response information should ignore it.
     pub response_information: Option<String>,
 
     /// If the reason code is `ServerMoved` or `UserAnotherServer`, the
@@ -126,7 +128,114 @@ impl Default for ConnAck {
     }
 }
 
+/// The effective values of a connection, once the `Connect` request of the
+/// client has been acknowledged by the server.
+/// See `ConnAck::resolve`.
+#[derive(PartialEq, Debug, Clone)]
+pub struct NegotiatedSettings {
+    /// The session expiry interval, in seconds.
+    pub session_expiry_interval: u32,
+
+    /// The maximum number of `AtLeastOnce` and `ExactlyOnce` messages the
+    /// server will concurrently treat for the client.
+    pub receive_maximum: u16,
+
+    /// The maximum quality of service the server accepts.
+    pub maximum_qos: QoS,
+
+    /// The maximum packet size the server accepts, if any.
+    pub maximum_packet_size: Option<u32>,
+
+    /// The highest topic alias the client can use.
+    pub topic_alias_maximum: u16,
+
+    /// The keep alive value, in seconds.
+    pub keep_alive: u16,
+}
+
 impl ConnAck {
+    /// Builds a `ConnAck` packet refusing a connection with the given reason
+    /// code, without any optional property. Fails with `ProtocolError` if the
+    /// reason code is not a `ConnAck` failure reason code.
+    pub fn rejected(reason: ReasonCode) -> SageResult<Self> {
+        if reason.is_error() && ReasonCode::try_parse(reason.as_u8(), PacketType::ConnAck).is_ok() {
+            Ok(ConnAck {
+                reason_code: reason,
+                ..Default::default()
+            })
+        } else {
+            Err(Error::Context(
+                ProtocolError,
+                format!("{:?} is not a valid CONNACK failure reason code", reason),
+            ))
+        }
+    }
+
+    /// Whether the server supports retained messages.
+    pub fn retain_supported(&self) -> bool {
+        self.retain_available
+    }
+
+    /// Whether the server supports subscribing with wildcards.
+    pub fn wildcard_supported(&self) -> bool {
+        self.wildcard_subscription_available
+    }
+
+    /// Whether the server supports shared subscriptions.
+    pub fn shared_supported(&self) -> bool {
+        self.shared_subscription_available
+    }
+
+    /// Whether the server supports subscription identifiers.
+    pub fn subscription_ids_supported(&self) -> bool {
+        self.subscription_identifiers_available
+    }
+
+    /// The maximum quality of service the server accepts.
+    pub fn max_qos(&self) -> QoS {
+        self.maximum_qos
+    }
+
+    /// Computes the effective values of the connection requested by
+    /// `connect`. Values absent from the `ConnAck` packet are either the ones
+    /// requested by the client, for session expiry interval and keep alive,
+    /// or the protocol defaults.
+    pub fn resolve(&self, connect: &Connect) -> NegotiatedSettings {
+        NegotiatedSettings {
+            session_expiry_interval: self
+                .session_expiry_interval
+                .or(connect.session_expiry_interval)
+                .unwrap_or_default(),
+            receive_maximum: self.receive_maximum,
+            maximum_qos: self.maximum_qos,
+            maximum_packet_size: self.maximum_packet_size,
+            topic_alias_maximum: self.topic_alias_maximum,
+            keep_alive: self.keep_alive.unwrap_or(connect.keep_alive),
+        }
+    }
+
+    /// Discards the reason string and user properties. Those are decoded
+    /// even if the client set `request_problem_information` to `false` in
+    /// its `Connect` packet, this lets the client enforce its request.
+    pub fn strip_problem_information(&mut self) {
+        self.reason_string = None;
+        self.user_properties.clear();
+    }
+
+    /// Sets the response information, only if `requested` is `true`, that is
+    /// if the client set `request_response_information` in its `Connect`
+    /// packet. The packet is returned unchanged otherwise.
+    pub fn with_response_information_if(self, requested: bool, info: String) -> Self {
+        if requested {
+            ConnAck {
+                response_information: Some(info),
+                ..self
+            }
+        } else {
+            self
+        }
+    }
+
     pub(crate) async fn write<W: AsyncWrite + Unpin>(self, mut writer: W) -> SageResult<usize> {
         let mut n_bytes = codec::write_bool(self.session_present, &mut writer).await?;
         n_bytes += codec::write_reason_code(self.reason_code, &mut writer).await?;
@@ -173,6 +282,12 @@ impl ConnAck {
         n_bytes += Property::WildcardSubscriptionAvailable(self.wildcard_subscription_available)
             .encode(&mut properties)
             .await?;
+        if self.subscription_identifiers_available != DEFAULT_SUBSCRIPTION_IDENTIFIER_AVAILABLE {
+            n_bytes +=
+                Property::SubscriptionIdentifiersAvailable(self.subscription_identifiers_available)
+                    .encode(&mut properties)
+                    .await?;
+        }
         n_bytes += Property::SharedSubscriptionAvailable(self.shared_subscription_available)
             .encode(&mut properties)
             .await?;
@@ -194,7 +309,7 @@ impl ConnAck {
                 .encode(&mut properties)
                 .await?;
             if !authentication.data.is_empty() {
-                n_bytes += Property::AuthenticationData(authentication.data)
+                n_bytes += Property::AuthenticationData(authentication.data.into())
                     .encode(&mut properties)
                     .await?;
             }
@@ -206,10 +321,21 @@ impl ConnAck {
         Ok(n_bytes)
     }
 
-    pub(crate) async fn read<R: AsyncRead + Unpin>(mut reader: R) -> SageResult<Self> {
+    pub(crate) async fn read<R: AsyncRead + Unpin>(
+        reader: R,
+        remaining_size: usize,
+        options: &DecodeOptions,
+    ) -> SageResult<Self> {
+        let mut reader = reader.take(remaining_size as u64);
+
         let session_present = codec::read_bool(&mut reader).await?;
 
-        let reason_code = codec::read_byte(&mut reader).await?.try_into()?;
+        let reason_code = codec::read_reason_code(&mut reader, PacketType::ConnAck).await?;
+
+        // A session cannot be present if the connection is refused
+        if session_present && reason_code.is_error() {
+            return Err(ProtocolError.into());
+        }
 
         let mut session_expiry_interval = None;
         let mut receive_maximum = DEFAULT_RECEIVE_MAXIMUM;
@@ -229,7 +355,8 @@ impl ConnAck {
         let mut authentication_method = None;
         let mut authentication_data = Default::default();
 
-        let mut decoder = PropertiesDecoder::take(reader).await?;
+        let mut decoder = PropertiesDecoder::take(&mut reader, options).await?;
+        decoder.check_bounds()?;
         while decoder.has_properties() {
             match decoder.read().await? {
                 Property::SessionExpiryInterval(v) => session_expiry_interval = Some(v),
@@ -260,7 +387,7 @@ impl ConnAck {
         let authentication = if let Some(method) = authentication_method {
             Some(Authentication {
                 method,
-                data: authentication_data,
+                data: BinaryData::try_new(authentication_data)?,
             })
         } else {
             if !authentication_data.is_empty() {
@@ -296,11 +423,12 @@ impl ConnAck {
 mod unit {
 
     use super::*;
+    use crate::{Error, ReasonCode::MalformedPacket};
     use std::io::Cursor;
 
     fn encoded() -> Vec<u8> {
         vec![
-            1, 138, 111, 17, 0, 0, 5, 57, 33, 0, 30, 36, 1, 37, 0, 39, 0, 0, 1, 0, 18, 0, 11, 87,
+            0, 138, 111, 17, 0, 0, 5, 57, 33, 0, 30, 36, 1, 37, 0, 39, 0, 0, 1, 0, 18, 0, 11, 87,
             97, 108, 107, 84, 104, 105, 115, 87, 97, 121, 34, 0, 10, 31, 0, 7, 82, 85, 78, 45, 68,
             77, 67, 38, 0, 7, 77, 111, 103, 119, 97, 195, 175, 0, 3, 67, 97, 116, 40, 0, 42, 0, 19,
             0, 17, 26, 0, 9, 65, 101, 114, 111, 115, 109, 105, 116, 104, 28, 0, 14, 80, 97, 105,
@@ -311,7 +439,7 @@ mod unit {
 
     fn decoded() -> ConnAck {
         ConnAck {
-            session_present: true,
+            session_present: false,
             reason_code: ReasonCode::Banned,
             session_expiry_interval: Some(1337),
             receive_maximum: 30,
@@ -330,7 +458,7 @@ mod unit {
             reference: Some("Paint It Black".into()),
             authentication: Some(Authentication {
                 method: "Willow".into(),
-                data: vec![0x0D, 0x15, 0xEA, 0x5E],
+                data: BinaryData::try_new(vec![0x0D, 0x15, 0xEA, 0x5E]).unwrap(),
             }),
         }
     }
@@ -347,7 +475,166 @@ mod unit {
     #[tokio::test]
     async fn decode() {
         let mut test_data = Cursor::new(encoded());
-        let tested_result = ConnAck::read(&mut test_data).await.unwrap();
+        let tested_result = ConnAck::read(&mut test_data, 114, &Default::default())
+            .await
+            .unwrap();
         assert_eq!(tested_result, decoded());
     }
+
+    #[tokio::test]
+    async fn response_information() {
+        let test_data = ConnAck {
+            response_information: Some("sage/responses".into()),
+            ..Default::default()
+        };
+        let mut encoded = Vec::new();
+        let n_bytes = test_data.clone().write(&mut encoded).await.unwrap();
+        let mut test_data_stream = Cursor::new(encoded);
+        let tested_result = ConnAck::read(&mut test_data_stream, n_bytes, &Default::default())
+            .await
+            .unwrap();
+        assert_eq!(
+            tested_result.response_information,
+            Some("sage/responses".into())
+        );
+        assert_eq!(tested_result, test_data);
+    }
+
+    #[test]
+    fn response_information_if() {
+        let connack =
+            ConnAck::default().with_response_information_if(true, "sage/responses".into());
+        assert_eq!(connack.response_information, Some("sage/responses".into()));
+        let connack =
+            ConnAck::default().with_response_information_if(false, "sage/responses".into());
+        assert_eq!(connack.response_information, None);
+    }
+
+    #[tokio::test]
+    async fn decode_property_length_exceeds_remaining_size() {
+        // The property block claims 3 bytes while only 2 remain in the packet.
+        let mut test_data = Cursor::new(vec![0, 0, 3, 33, 0, 30, 0xC0, 0x00]);
+        assert!(matches!(
+            ConnAck::read(&mut test_data, 5, &Default::default()).await,
+            Err(Error::Reason(MalformedPacket))
+        ));
+    }
+
+    #[tokio::test]
+    async fn decode_session_present_with_error() {
+        let mut test_data = Cursor::new(vec![1, 0x8A, 0]);
+        assert!(matches!(
+            ConnAck::read(&mut test_data, 3, &Default::default()).await,
+            Err(Error::Reason(ProtocolError))
+        ));
+    }
+
+    #[tokio::test]
+    async fn decode_session_present_with_success() {
+        let mut test_data = Cursor::new(vec![1, 0x00, 0]);
+        let tested_result = ConnAck::read(&mut test_data, 3, &Default::default())
+            .await
+            .unwrap();
+        assert!(tested_result.session_present);
+        assert_eq!(tested_result.reason_code, ReasonCode::Success);
+    }
+
+    #[test]
+    fn wildcard_not_supported() {
+        let connack = ConnAck {
+            wildcard_subscription_available: false,
+            ..Default::default()
+        };
+        assert!(!connack.wildcard_supported());
+        assert!(connack.retain_supported());
+        assert!(connack.shared_supported());
+        assert!(connack.subscription_ids_supported());
+        assert_eq!(connack.max_qos(), QoS::ExactlyOnce);
+    }
+
+    #[test]
+    fn strip_problem_information() {
+        let mut connack = decoded();
+        connack.strip_problem_information();
+        assert_eq!(connack.reason_string, None);
+        assert!(connack.user_properties.is_empty());
+        assert_eq!(connack.assigned_client_id, Some("WalkThisWay".into()));
+    }
+
+    #[test]
+    fn resolve() {
+        let connect = Connect {
+            keep_alive: 60,
+            session_expiry_interval: Some(3600),
+            ..Connect::anonymous()
+        };
+        let connack = ConnAck {
+            keep_alive: Some(30),
+            topic_alias_maximum: 10,
+            ..Default::default()
+        };
+        assert_eq!(
+            connack.resolve(&connect),
+            NegotiatedSettings {
+                session_expiry_interval: 3600,
+                receive_maximum: DEFAULT_RECEIVE_MAXIMUM,
+                maximum_qos: DEFAULT_MAXIMUM_QOS,
+                maximum_packet_size: None,
+                topic_alias_maximum: 10,
+                keep_alive: 30,
+            }
+        );
+    }
+
+    #[test]
+    fn rejected() {
+        let connack = ConnAck::rejected(ReasonCode::NotAuthorized).unwrap();
+        assert!(!connack.session_present);
+        assert_eq!(connack.reason_code, ReasonCode::NotAuthorized);
+        assert_eq!(connack.session_expiry_interval, None);
+        assert_eq!(connack.assigned_client_id, None);
+    }
+
+    #[test]
+    fn rejected_invalid_reason_code() {
+        for reason_code in &[ReasonCode::Success, ReasonCode::SessionTakenOver] {
+            assert!(matches!(
+                ConnAck::rejected(*reason_code),
+                Err(Error::Context(ProtocolError, _))
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn topic_alias_maximum_default_omitted() {
+        let mut encoded_default = Vec::new();
+        let default_size = ConnAck::default()
+            .write(&mut encoded_default)
+            .await
+            .unwrap();
+
+        let mut encoded = Vec::new();
+        let n_bytes = ConnAck {
+            topic_alias_maximum: 10,
+            ..ConnAck::default()
+        }
+        .write(&mut encoded)
+        .await
+        .unwrap();
+        // The property identifier followed by a two bytes integer
+        assert_eq!(n_bytes, default_size + 3);
+        assert!(encoded.windows(3).any(|bytes| bytes == [0x22, 0, 10]));
+
+        let mut test_data = Cursor::new(encoded_default);
+        let tested_result = ConnAck::read(&mut test_data, default_size, &Default::default())
+            .await
+            .unwrap();
+        assert_eq!(tested_result.topic_alias_maximum, 0);
+
+        let mut test_data = Cursor::new(encoded);
+        let tested_result = ConnAck::read(&mut test_data, n_bytes, &Default::default())
+            .await
+            .unwrap();
+        assert_eq!(tested_result.topic_alias_maximum, 10);
+    }
 }
@@ -5,12 +5,12 @@ use crate::{
         DEFAULT_REQUEST_PROBLEM_INFORMATION, DEFAULT_REQUEST_RESPONSE_INFORMATION,
         DEFAULT_TOPIC_ALIAS_MAXIMUM, DEFAULT_WILL_DELAY_INTERVAL,
     },
-    Authentication, ClientID, PropertiesDecoder, Property, QoS,
-    ReasonCode::{ClientIdentifierNotValid, MalformedPacket, ProtocolError},
+    Authentication, BinaryData, ClientID, DecodeOptions, Error, PropertiesDecoder, Property, QoS,
+    ReasonCode::{ClientIdentifierNotValid, MalformedPacket, ProtocolError, RetainNotSupported},
     Result as SageResult, Topic, Will,
 };
-use std::{convert::TryInto, marker::Unpin};
-use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
+use std::{convert::TryInto, marker::Unpin, time::Duration};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 
 /// The `Connect` control packet is used to open a session. It is the first
 /// Packet a client must send to a server once the connection is established.
@@ -42,7 +42,7 @@ pub struct Connect {
     pub user_name: Option<String>,
 
     /// An option password to send to the server.
-    pub password: Option<Vec<u8>>,
+    pub password: Option<BinaryData>,
 
     /// Specifies the maximum amount of time the client and the server may not
     /// communicate with each other. This value is expressed in seconds.
@@ -144,6 +144,38 @@ impl Default for Connect {
     }
 }
 
+/// The state of a connection as seen by a server once a `Connect` packet was
+/// received. See `Connect::into_context`.
+#[derive(PartialEq, Debug, Clone)]
+pub struct ConnectionContext {
+    /// The client identifier, either the one given by the client or one
+    /// assigned by the server.
+    pub client_id: ClientID,
+
+    /// `true` if the client identifier was assigned by the server, in which
+    /// case it must be sent back in the `ConnAck` packet.
+    pub assigned_client_id: bool,
+
+    /// Whether the session starts anew.
+    pub clean_start: bool,
+
+    /// The keep alive value, in seconds.
+    pub keep_alive: u16,
+
+    /// The session expiry interval, in seconds.
+    pub session_expiry_interval: u32,
+
+    /// The maximum number of `AtLeastOnce` and `ExactlyOnce` messages the
+    /// client will concurrently treat.
+    pub receive_maximum: u16,
+
+    /// The maximum packet size the client accepts, if any.
+    pub maximum_packet_size: Option<u32>,
+
+    /// The highest topic alias the server can use.
+    pub topic_alias_maximum: u16,
+}
+
 #[derive(Debug)]
 struct ConnectFlags {
     pub clean_start: bool,
@@ -154,8 +186,193 @@ struct ConnectFlags {
     pub password: bool,
 }
 
+fn validate_client_id(client_id: &str) -> SageResult<()> {
+    if client_id.is_empty()
+        || client_id.len() > 23
+        || !client_id.chars().all(|c| c.is_ascii_alphanumeric())
+    {
+        Err(ClientIdentifierNotValid.into())
+    } else {
+        Ok(())
+    }
+}
+
 impl Connect {
+    /// Builds a minimal valid `Connect` packet without any client identifier,
+    /// letting the server assign one. This requires a clean start.
+    pub fn anonymous() -> Self {
+        Connect {
+            clean_start: true,
+            ..Default::default()
+        }
+    }
+
+    /// Builds a minimal `Connect` packet with the given client identifier.
+    /// The identifier must be between 1 and 23 characters long and made of
+    /// ASCII letters and digits only, as the specification requires servers
+    /// to accept, otherwise `ClientIdentifierNotValid` is returned.
+    pub fn with_id(id: &str) -> SageResult<Self> {
+        validate_client_id(id)?;
+        Ok(Connect {
+            client_id: Some(id.into()),
+            ..Default::default()
+        })
+    }
+
+    /// Whether the client asked for enhanced authentication by giving an
+    /// authentication method. `Auth` packets may only be exchanged on
+    /// connections where this is the case.
+    pub fn uses_enhanced_auth(&self) -> bool {
+        matches!(&self.authentication, Some(authentication) if !authentication.method.is_empty())
+    }
+
+    /// Checks the will of the packet against the support of retained messages
+    /// by the server, given by `retain_available`. Fails with
+    /// `RetainNotSupported` if the will is to be retained but the server does
+    /// not support it.
+    pub fn validate_will_retain(&self, retain_available: bool) -> SageResult<()> {
+        match &self.will {
+            Some(will) if will.retain && !retain_available => Err(RetainNotSupported.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Rebuilds the list of properties of the packet from its fields, in the
+    /// order they are encoded. Will properties belong to the payload and are
+    /// not listed.
+    pub fn decoded_properties(&self) -> Vec<Property> {
+        let mut properties = Vec::new();
+        if let Some(v) = self.session_expiry_interval {
+            properties.push(Property::SessionExpiryInterval(v));
+        }
+        properties.push(Property::ReceiveMaximum(self.receive_maximum));
+        if let Some(v) = self.maximum_packet_size {
+            properties.push(Property::MaximumPacketSize(v));
+        }
+        properties.push(Property::TopicAliasMaximum(self.topic_alias_maximum));
+        properties.push(Property::RequestResponseInformation(
+            self.request_response_information,
+        ));
+        properties.push(Property::RequestProblemInformation(
+            self.request_problem_information,
+        ));
+        for (k, v) in &self.user_properties {
+            properties.push(Property::UserProperty(k.clone(), v.clone()));
+        }
+        if let Some(authentication) = &self.authentication {
+            properties.push(Property::AuthenticationMethod(
+                authentication.method.clone(),
+            ));
+            if !authentication.data.is_empty() {
+                properties.push(Property::AuthenticationData(authentication.data.to_vec()));
+            }
+        }
+        properties
+    }
+
+    /// Builds the state of the connection opened by this packet. If the
+    /// client did not give any identifier, `assign_id` is called to generate
+    /// one.
+    pub fn into_context(self, assign_id: impl FnOnce() -> String) -> ConnectionContext {
+        let (client_id, assigned_client_id) = match self.client_id {
+            Some(client_id) if !client_id.is_empty() => (client_id, false),
+            _ => (assign_id(), true),
+        };
+        ConnectionContext {
+            client_id,
+            assigned_client_id,
+            clean_start: self.clean_start,
+            keep_alive: self.keep_alive,
+            session_expiry_interval: self.session_expiry_interval.unwrap_or_default(),
+            receive_maximum: self.receive_maximum,
+            maximum_packet_size: self.maximum_packet_size,
+            topic_alias_maximum: self.topic_alias_maximum,
+        }
+    }
+
+    /// The keep alive value as a `Duration`.
+    pub fn keep_alive_duration(&self) -> Duration {
+        Duration::from_secs(self.keep_alive as u64)
+    }
+
+    /// Sets the keep alive value from a `Duration`. Fractions of seconds are
+    /// truncated and the value saturates at `u16::MAX` seconds.
+    pub fn set_keep_alive_duration(&mut self, duration: Duration) {
+        self.keep_alive = duration.as_secs().try_into().unwrap_or(u16::MAX);
+    }
+
+    /// The session expiry interval as a `Duration`, if any.
+    pub fn session_expiry_duration(&self) -> Option<Duration> {
+        self.session_expiry_interval
+            .map(|v| Duration::from_secs(v as u64))
+    }
+
+    /// Sets the session expiry interval from a `Duration`. Fractions of
+    /// seconds are truncated and the value saturates at `u32::MAX` seconds,
+    /// meaning the session never expires.
+    pub fn set_session_expiry_duration(&mut self, duration: Option<Duration>) {
+        self.session_expiry_interval = duration.map(|d| d.as_secs().try_into().unwrap_or(u32::MAX));
+    }
+
+    /// Checks the `Connect` content against the rules that cannot be enforced
+    /// by types alone, returning the first violation found.
+    /// - An empty or missing client id requires a clean start
+    /// - The client id, if any, must be between 1 and 23 characters long
+    ///   and made of ASCII letters and digits
+    /// - The receive maximum must not be 0
+    /// - The maximum packet size, if any, must not be 0
+    /// - The will topic, if any, must not be empty nor contain wildcards
+    pub fn validate(&self) -> SageResult<()> {
+        match &self.client_id {
+            Some(client_id) if !client_id.is_empty() => {
+                validate_client_id(client_id).map_err(|_| {
+                    Error::Context(
+                        ClientIdentifierNotValid,
+                        "client id must be 1 to 23 ASCII letters and digits".into(),
+                    )
+                })?;
+            }
+            // Requesting a server assigned client id requires a clean start
+            _ if !self.clean_start => {
+                return Err(Error::Context(
+                    ProtocolError,
+                    "an empty client id requires clean start".into(),
+                ));
+            }
+            _ => (),
+        }
+        if self.receive_maximum == 0 {
+            return Err(Error::Context(
+                ProtocolError,
+                "receive maximum must not be 0".into(),
+            ));
+        }
+        if self.maximum_packet_size == Some(0) {
+            return Err(Error::Context(
+                ProtocolError,
+                "maximum packet size must not be 0".into(),
+            ));
+        }
+        if let Some(will) = &self.will {
+            if will.topic == Topic::default() {
+                return Err(Error::Context(
+                    ProtocolError,
+                    "will topic must not be empty".into(),
+                ));
+            }
+            if will.topic.has_wildcards() {
+                return Err(Error::Context(
+                    ProtocolError,
+                    "will topic must not contain wildcards".into(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
     pub(crate) async fn write<W: AsyncWrite + Unpin>(self, mut writer: W) -> SageResult<usize> {
+        self.validate()?;
+
         // Variable Header (into content)
         let mut n_bytes = codec::write_utf8_string("MQTT", &mut writer).await?;
         n_bytes += codec::write_byte(0x05, &mut writer).await?;
@@ -217,15 +434,14 @@ impl Connect {
         writer.write_all(&properties).await?;
 
         // Payload
-        if let Some(client_id) = self.client_id {
-            if client_id.len() > 23 || client_id.chars().any(|c| !('0'..='z').contains(&c)) {
-                return Err(MalformedPacket.into());
-            }
-            n_bytes += codec::write_utf8_string(&client_id, &mut writer).await?;
-        } else {
-            // Still write empty client id
-            n_bytes += codec::write_utf8_string("", &mut writer).await?;
-        }
+        // A missing client id is written as an empty string. Either way, an
+        // empty id has been rejected by `validate` unless clean start is set.
+        let client_id = self.client_id.unwrap_or_default();
+        debug_assert!(
+            self.clean_start || !client_id.is_empty(),
+            "an empty client id cannot be written without clean start"
+        );
+        n_bytes += codec::write_utf8_string(&client_id, &mut writer).await?;
 
         if let Some(w) = self.will {
             let mut properties = Vec::new();
@@ -241,16 +457,20 @@ impl Connect {
                     .encode(&mut properties)
                     .await?;
             }
-            n_bytes += Property::ContentType(w.content_type)
-                .encode(&mut properties)
-                .await?;
+            if !w.content_type.is_empty() {
+                n_bytes += Property::ContentType(w.content_type)
+                    .encode(&mut properties)
+                    .await?;
+            }
             if let Some(response_topic) = w.response_topic {
                 n_bytes += Property::ResponseTopic(response_topic)
                     .encode(&mut properties)
                     .await?;
             }
             if let Some(v) = w.correlation_data {
-                n_bytes += Property::CorrelationData(v).encode(&mut properties).await?;
+                n_bytes += Property::CorrelationData(v.into())
+                    .encode(&mut properties)
+                    .await?;
             }
             for (k, v) in w.user_properties {
                 n_bytes += Property::UserProperty(k, v).encode(&mut properties).await?;
@@ -274,7 +494,28 @@ impl Connect {
         Ok(n_bytes)
     }
 
-    pub(crate) async fn read<R: AsyncRead + Unpin>(mut reader: R) -> SageResult<Self> {
+    /// Reads the body of a `Connect` packet, that is everything following
+    /// the fixed header, of `remaining_size` bytes. Unlike `Packet::decode`,
+    /// the client identifier is not checked against length and character
+    /// restrictions, which lets pass-through tools forward any client.
+    pub async fn read_lenient<R: AsyncRead + Unpin>(
+        reader: R,
+        remaining_size: usize,
+    ) -> SageResult<Self> {
+        let options = DecodeOptions {
+            lenient_client_id: true,
+            ..Default::default()
+        };
+        Connect::read(reader, remaining_size, &options).await
+    }
+
+    pub(crate) async fn read<R: AsyncRead + Unpin>(
+        reader: R,
+        remaining_size: usize,
+        options: &DecodeOptions,
+    ) -> SageResult<Self> {
+        let mut reader = reader.take(remaining_size as u64);
+
         let protocol_name = codec::read_utf8_string(&mut reader).await?;
         if protocol_name != "MQTT" {
             return Err(MalformedPacket.into());
@@ -301,7 +542,8 @@ impl Connect {
         let mut authentication_method = None;
         let mut authentication_data = Default::default();
 
-        let mut decoder = PropertiesDecoder::take(&mut reader).await?;
+        let mut decoder = PropertiesDecoder::take(&mut reader, options).await?;
+        decoder.check_bounds()?;
 
         while decoder.has_properties() {
             match decoder.read().await? {
@@ -314,7 +556,12 @@ impl Connect {
                 Property::AuthenticationMethod(v) => authentication_method = Some(v),
                 Property::AuthenticationData(v) => authentication_data = v,
                 Property::UserProperty(k, v) => user_properties.push((k, v)),
-                _ => return Err(ProtocolError.into()),
+                p => {
+                    return Err(Error::Context(
+                        ProtocolError,
+                        format!("{:?} property is not allowed in CONNECT", p.id()),
+                    ))
+                }
             };
         }
         let reader = decoder.into_inner();
@@ -322,7 +569,7 @@ impl Connect {
         let authentication = if let Some(method) = authentication_method {
             Some(Authentication {
                 method,
-                data: authentication_data,
+                data: BinaryData::try_new(authentication_data)?,
             })
         } else {
             if !authentication_data.is_empty() {
@@ -337,13 +584,18 @@ impl Connect {
             if client_id.is_empty() {
                 None
             } else {
-                if client_id.len() > 23 || client_id.chars().any(|c| !('0'..='z').contains(&c)) {
-                    return Err(ClientIdentifierNotValid.into());
+                if !options.lenient_client_id {
+                    validate_client_id(&client_id)?;
                 }
                 Some(client_id)
             }
         };
 
+        // Requesting a server assigned client id requires a clean start
+        if client_id.is_none() && !clean_start {
+            return Err(ProtocolError.into());
+        }
+
         let (reader, will) = if flags.will {
             let mut delay_interval = DEFAULT_WILL_DELAY_INTERVAL;
             let mut payload_format_indicator = DEFAULT_PAYLOAD_FORMAT_INDICATOR;
@@ -353,7 +605,8 @@ impl Connect {
             let mut correlation_data = None;
             let mut user_properties = Vec::new();
 
-            let mut decoder = PropertiesDecoder::take(reader).await?;
+            let mut decoder = PropertiesDecoder::take(reader, options).await?;
+            decoder.check_bounds()?;
             while decoder.has_properties() {
                 match decoder.read().await? {
                     Property::WillDelayInterval(v) => delay_interval = v,
@@ -361,9 +614,16 @@ impl Connect {
                     Property::MessageExpiryInterval(v) => message_expiry_interval = Some(v),
                     Property::ContentType(v) => content_type = v,
                     Property::ResponseTopic(v) => response_topic = Some(v),
-                    Property::CorrelationData(v) => correlation_data = Some(v),
+                    Property::CorrelationData(v) => {
+                        correlation_data = Some(BinaryData::try_new(v)?)
+                    }
                     Property::UserProperty(k, v) => user_properties.push((k, v)),
-                    _ => return Err(ProtocolError.into()),
+                    p => {
+                        return Err(Error::Context(
+                            ProtocolError,
+                            format!("{:?} property is not allowed in will properties", p.id()),
+                        ))
+                    }
                 }
             }
             let reader = decoder.into_inner();
@@ -396,7 +656,7 @@ impl Connect {
         };
 
         let password = if flags.password {
-            Some(codec::read_binary_data(reader).await?)
+            Some(BinaryData::try_new(codec::read_binary_data(reader).await?)?)
         } else {
             None
         };
@@ -435,7 +695,10 @@ impl ConnectFlags {
         let bits = codec::read_byte(reader).await?;
 
         if bits & 0x01 != 0 {
-            Err(MalformedPacket.into())
+            Err(Error::Context(
+                MalformedPacket,
+                "reserved bit set in CONNECT flags".into(),
+            ))
         } else {
             Ok(ConnectFlags {
                 user_name: (bits & 0b1000_0000) >> 7 > 0,
@@ -457,9 +720,9 @@ mod unit {
 
     fn encoded() -> Vec<u8> {
         vec![
-            0, 4, 77, 81, 84, 84, 5, 206, 0, 10, 5, 17, 0, 0, 0, 10, 0, 0, 3, 3, 0, 0, 0, 6, 67,
-            108, 111, 90, 101, 101, 0, 6, 79, 114, 101, 103, 111, 110, 0, 6, 87, 105, 108, 108,
-            111, 119, 0, 5, 74, 97, 100, 101, 110,
+            0, 4, 77, 81, 84, 84, 5, 206, 0, 10, 5, 17, 0, 0, 0, 10, 0, 0, 0, 0, 6, 67, 108, 111,
+            90, 101, 101, 0, 6, 79, 114, 101, 103, 111, 110, 0, 6, 87, 105, 108, 108, 111, 119, 0,
+            5, 74, 97, 100, 101, 110,
         ]
     }
 
@@ -472,7 +735,7 @@ mod unit {
             clean_start: true,
             session_expiry_interval,
             user_name: Some("Willow".into()),
-            password: Some("Jaden".into()),
+            password: Some(BinaryData::try_new("Jaden").unwrap()),
             will: Some(Will {
                 qos: QoS::AtLeastOnce,
                 ..Will::with_message(Topic::from("CloZee"), "Oregon")
@@ -484,6 +747,7 @@ mod unit {
     #[tokio::test]
     async fn encode_default_auth() {
         let test_data = Connect {
+            clean_start: true,
             authentication: Some(Default::default()),
             ..Default::default()
         };
@@ -492,24 +756,233 @@ mod unit {
         let n_bytes = test_data.write(&mut tested_result).await.unwrap();
         assert_eq!(
             tested_result,
-            vec![0, 4, 77, 81, 84, 84, 5, 0, 2, 88, 3, 21, 0, 0, 0, 0]
+            vec![0, 4, 77, 81, 84, 84, 5, 2, 2, 88, 3, 21, 0, 0, 0, 0]
         );
         assert_eq!(n_bytes, 16);
     }
 
     #[tokio::test]
     async fn decode_default_auth() {
-        let mut test_data = Cursor::new(vec![0, 4, 77, 81, 84, 84, 5, 0, 2, 88, 3, 21, 0, 0, 0, 0]);
-        let tested_result = Connect::read(&mut test_data).await.unwrap();
+        let mut test_data = Cursor::new(vec![0, 4, 77, 81, 84, 84, 5, 2, 2, 88, 3, 21, 0, 0, 0, 0]);
+        let tested_result = Connect::read(&mut test_data, 16, &Default::default())
+            .await
+            .unwrap();
         assert_eq!(
             tested_result,
             Connect {
+                clean_start: true,
                 authentication: Some(Default::default()),
                 ..Default::default()
             }
         );
     }
 
+    #[tokio::test]
+    async fn request_response_information() {
+        let test_data = Connect {
+            clean_start: true,
+            request_response_information: true,
+            ..Default::default()
+        };
+        let mut encoded = Vec::new();
+        let n_bytes = test_data.clone().write(&mut encoded).await.unwrap();
+        assert_eq!(
+            encoded,
+            vec![0, 4, 77, 81, 84, 84, 5, 2, 2, 88, 2, 25, 1, 0, 0]
+        );
+        assert_eq!(n_bytes, 15);
+
+        let mut test_data_stream = Cursor::new(encoded);
+        let tested_result = Connect::read(&mut test_data_stream, 15, &Default::default())
+            .await
+            .unwrap();
+        assert!(tested_result.request_response_information);
+        assert_eq!(tested_result, test_data);
+    }
+
+    #[tokio::test]
+    async fn anonymous() {
+        let connect = Connect::anonymous();
+        assert!(connect.clean_start);
+        assert_eq!(connect.client_id, None);
+
+        let mut encoded = Vec::new();
+        let n_bytes = connect.clone().write(&mut encoded).await.unwrap();
+        let mut test_data = Cursor::new(encoded);
+        assert_eq!(
+            Connect::read(&mut test_data, n_bytes, &Default::default())
+                .await
+                .unwrap(),
+            connect
+        );
+    }
+
+    #[test]
+    fn with_id() {
+        assert_eq!(
+            Connect::with_id("Sage451").unwrap().client_id,
+            Some("Sage451".into())
+        );
+        for id in &["", "Sage/451", "Sage_451", "ALongerThanTwentyThreeId"] {
+            assert!(matches!(
+                Connect::with_id(id),
+                Err(Error::Reason(ClientIdentifierNotValid))
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn read_lenient() {
+        let connect = Connect {
+            clean_start: true,
+            client_id: Some("sage/client#1".into()),
+            ..Default::default()
+        };
+
+        // Built by hand as write refuses such a client id
+        let mut encoded = vec![0, 4, 77, 81, 84, 84, 5, 2, 2, 88, 0, 0, 13];
+        encoded.extend_from_slice(b"sage/client#1");
+        let remaining_size = encoded.len();
+
+        assert!(matches!(
+            Connect::read(
+                &mut Cursor::new(&encoded),
+                remaining_size,
+                &Default::default()
+            )
+            .await,
+            Err(Error::Reason(ClientIdentifierNotValid))
+        ));
+        let tested_result = Connect::read_lenient(&mut Cursor::new(&encoded), remaining_size)
+            .await
+            .unwrap();
+        assert_eq!(tested_result, connect);
+    }
+
+    #[tokio::test]
+    async fn encode_no_client_id_without_clean_start() {
+        let test_data = Connect {
+            clean_start: false,
+            client_id: None,
+            ..Default::default()
+        };
+        let mut tested_result = Vec::new();
+        assert!(matches!(
+            test_data.write(&mut tested_result).await,
+            Err(Error::Context(ProtocolError, _))
+        ));
+    }
+
+    #[tokio::test]
+    async fn encode_empty_client_id_without_clean_start() {
+        let test_data = Connect {
+            clean_start: false,
+            client_id: Some(String::new()),
+            ..Default::default()
+        };
+        let mut tested_result = Vec::new();
+        assert!(matches!(
+            test_data.write(&mut tested_result).await,
+            Err(Error::Context(ProtocolError, _))
+        ));
+    }
+
+    #[tokio::test]
+    async fn decode_no_client_id_without_clean_start() {
+        let mut test_data = Cursor::new(vec![0, 4, 77, 81, 84, 84, 5, 0, 2, 88, 0, 0, 0]);
+        assert!(matches!(
+            Connect::read(&mut test_data, 13, &Default::default()).await,
+            Err(Error::Reason(ProtocolError))
+        ));
+    }
+
+    #[tokio::test]
+    async fn decode_assigned_client_identifier() {
+        let mut test_data =
+            Cursor::new(vec![0, 4, 77, 81, 84, 84, 5, 2, 2, 88, 3, 0x12, 0, 0, 0, 0]);
+        assert!(matches!(
+            Connect::read(&mut test_data, 16, &Default::default()).await,
+            Err(Error::Context(ProtocolError, message)) if message.contains("AssignedClientIdentifier")
+        ));
+    }
+
+    #[tokio::test]
+    async fn decode_server_keep_alive() {
+        let mut test_data = Cursor::new(vec![
+            0, 4, 77, 81, 84, 84, 5, 2, 2, 88, 3, 0x13, 0, 10, 0, 0,
+        ]);
+        assert!(matches!(
+            Connect::read(&mut test_data, 16, &Default::default()).await,
+            Err(Error::Context(ProtocolError, message)) if message.contains("ServerKeepAlive")
+        ));
+    }
+
+    #[tokio::test]
+    async fn no_client_id_with_clean_start() {
+        let test_data = Connect {
+            clean_start: true,
+            client_id: None,
+            ..Default::default()
+        };
+        let mut encoded = Vec::new();
+        test_data.clone().write(&mut encoded).await.unwrap();
+        assert_eq!(encoded, vec![0, 4, 77, 81, 84, 84, 5, 2, 2, 88, 0, 0, 0]);
+
+        let mut test_stream = Cursor::new(encoded);
+        let tested_result = Connect::read(&mut test_stream, 13, &Default::default())
+            .await
+            .unwrap();
+        assert_eq!(tested_result, test_data);
+    }
+
+    #[tokio::test]
+    async fn encode_no_client_id_writes_empty_string() {
+        let test_data = Connect {
+            clean_start: true,
+            client_id: None,
+            user_name: Some("Willow".into()),
+            ..Default::default()
+        };
+        let mut encoded = Vec::new();
+        test_data.write(&mut encoded).await.unwrap();
+        assert_eq!(
+            encoded[11..],
+            [0x00, 0x00, 0, 6, 87, 105, 108, 108, 111, 119]
+        );
+    }
+
+    #[test]
+    fn keep_alive_duration() {
+        let mut connect = Connect::default();
+        connect.set_keep_alive_duration(Duration::from_secs(600));
+        assert_eq!(connect.keep_alive, 600);
+        assert_eq!(connect.keep_alive_duration(), Duration::from_secs(600));
+        connect.set_keep_alive_duration(Duration::from_secs(100_000));
+        assert_eq!(connect.keep_alive, u16::MAX);
+    }
+
+    #[test]
+    fn session_expiry_duration() {
+        let mut connect = Connect::default();
+        connect.set_session_expiry_duration(Some(Duration::from_secs(600)));
+        assert_eq!(connect.session_expiry_interval, Some(600));
+        assert_eq!(
+            connect.session_expiry_duration(),
+            Some(Duration::from_secs(600))
+        );
+        connect.set_session_expiry_duration(Some(Duration::from_secs(u64::MAX)));
+        assert_eq!(connect.session_expiry_interval, Some(u32::MAX));
+    }
+
+    #[tokio::test]
+    async fn decode_reserved_flag() {
+        let mut test_data = Cursor::new(vec![0x01]);
+        assert!(matches!(
+            ConnectFlags::read(&mut test_data).await,
+            Err(Error::Context(MalformedPacket, message)) if message == "reserved bit set in CONNECT flags"
+        ));
+    }
+
     #[tokio::test]
     async fn encode() {
         let test_data = decoded();
@@ -517,13 +990,270 @@ mod unit {
 
         let n_bytes = test_data.write(&mut tested_result).await.unwrap();
         assert_eq!(tested_result, encoded());
-        assert_eq!(n_bytes, 53);
+        assert_eq!(n_bytes, 50);
     }
 
     #[tokio::test]
     async fn decode() {
         let mut test_data = Cursor::new(encoded());
-        let tested_result = Connect::read(&mut test_data).await.unwrap();
+        let tested_result = Connect::read(&mut test_data, 50, &Default::default())
+            .await
+            .unwrap();
         assert_eq!(tested_result, decoded());
     }
+
+    #[tokio::test]
+    async fn will_empty_payload() {
+        let test_data = Connect {
+            clean_start: true,
+            will: Some(Will::with_message(Topic::from("CloZee"), "")),
+            ..Default::default()
+        };
+        // The will properties all have their default value, leaving an
+        // empty will property block
+        let encoded = vec![
+            0, 4, 77, 81, 84, 84, 5, 6, 2, 88, 0, 0, 0, 0, 0, 6, 67, 108, 111, 90, 101, 101, 0, 0,
+        ];
+
+        let mut tested_result = Vec::new();
+        let n_bytes = test_data.clone().write(&mut tested_result).await.unwrap();
+        assert_eq!(tested_result, encoded);
+        assert_eq!(n_bytes, 24);
+
+        let mut test_stream = Cursor::new(encoded);
+        let tested_result = Connect::read(&mut test_stream, 24, &Default::default())
+            .await
+            .unwrap();
+        assert_eq!(tested_result, test_data);
+        assert_eq!(tested_result.will.unwrap().message, Vec::<u8>::new());
+    }
+
+    #[tokio::test]
+    async fn decode_property_length_exceeds_remaining_size() {
+        // The property block claims 10 bytes while only the 2 bytes of the
+        // empty client id remain. Whatever follows belongs to another packet.
+        let mut test_data = Cursor::new(vec![
+            0, 4, 77, 81, 84, 84, 5, 2, 0, 10, 10, 0, 0, 0xC0, 0x00, 0xC0, 0x00, 0xC0, 0x00, 0xC0,
+            0x00,
+        ]);
+        assert!(matches!(
+            Connect::read(&mut test_data, 13, &Default::default()).await,
+            Err(Error::Reason(MalformedPacket))
+        ));
+    }
+
+    #[tokio::test]
+    async fn decode_property_length_shorter_than_properties() {
+        // The property block claims 3 bytes, leaving the Topic Alias Maximum
+        // property in the payload where it is read as the client id length.
+        // The bytes following the packet would be enough to satisfy that
+        // length, but they must not be read.
+        let mut packet = vec![
+            0, 4, 77, 81, 84, 84, 5, 2, 0, 10, 3, 0x21, 0, 10, 0x22, 0, 5, 0, 0,
+        ];
+        let remaining_size = packet.len();
+        packet.extend(vec![b'a'; 0x2200]);
+        let mut test_data = Cursor::new(packet);
+        assert!(
+            Connect::read(&mut test_data, remaining_size, &Default::default())
+                .await
+                .is_err()
+        );
+        assert_eq!(test_data.position(), remaining_size as u64);
+    }
+
+    #[test]
+    fn into_context_assigns_id() {
+        let context = Connect {
+            keep_alive: 30,
+            ..Connect::anonymous()
+        }
+        .into_context(|| "Sage451".into());
+        assert_eq!(context.client_id, "Sage451");
+        assert!(context.assigned_client_id);
+        assert_eq!(context.keep_alive, 30);
+        assert_eq!(context.session_expiry_interval, 0);
+        assert_eq!(context.receive_maximum, DEFAULT_RECEIVE_MAXIMUM);
+    }
+
+    #[test]
+    fn into_context_keeps_id() {
+        let context = Connect::with_id("Jaden")
+            .unwrap()
+            .into_context(|| unreachable!());
+        assert_eq!(context.client_id, "Jaden");
+        assert!(!context.assigned_client_id);
+    }
+
+    #[tokio::test]
+    async fn topic_alias_maximum_default_omitted() {
+        let mut encoded_default = Vec::new();
+        let default_size = Connect::anonymous()
+            .write(&mut encoded_default)
+            .await
+            .unwrap();
+
+        let mut encoded = Vec::new();
+        let n_bytes = Connect {
+            topic_alias_maximum: 10,
+            ..Connect::anonymous()
+        }
+        .write(&mut encoded)
+        .await
+        .unwrap();
+        // The property identifier followed by a two bytes integer
+        assert_eq!(n_bytes, default_size + 3);
+        assert!(encoded.windows(3).any(|bytes| bytes == [0x22, 0, 10]));
+
+        let mut test_data = Cursor::new(encoded_default);
+        let tested_result = Connect::read(&mut test_data, default_size, &Default::default())
+            .await
+            .unwrap();
+        assert_eq!(tested_result.topic_alias_maximum, 0);
+
+        let mut test_data = Cursor::new(encoded);
+        let tested_result = Connect::read(&mut test_data, n_bytes, &Default::default())
+            .await
+            .unwrap();
+        assert_eq!(tested_result.topic_alias_maximum, 10);
+    }
+
+    #[tokio::test]
+    async fn encode_invalid() {
+        let invalid_connects = vec![
+            (
+                Connect {
+                    client_id: Some("sage/client#1".into()),
+                    ..Default::default()
+                },
+                ClientIdentifierNotValid,
+            ),
+            (
+                Connect {
+                    receive_maximum: 0,
+                    ..Connect::anonymous()
+                },
+                ProtocolError,
+            ),
+            (
+                Connect {
+                    maximum_packet_size: Some(0),
+                    ..Connect::anonymous()
+                },
+                ProtocolError,
+            ),
+            (
+                Connect {
+                    will: Some(Will::with_message(Topic::from("clients/+"), "offline")),
+                    ..Connect::anonymous()
+                },
+                ProtocolError,
+            ),
+        ];
+        for (connect, reason_code) in invalid_connects {
+            assert!(matches!(
+                connect.validate(),
+                Err(Error::Context(rc, _)) if rc == reason_code
+            ));
+            let mut tested_result = Vec::new();
+            assert!(matches!(
+                connect.write(&mut tested_result).await,
+                Err(Error::Context(rc, _)) if rc == reason_code
+            ));
+            assert!(tested_result.is_empty());
+        }
+        assert!(Connect::anonymous().validate().is_ok());
+    }
+
+    #[test]
+    fn uses_enhanced_auth() {
+        assert!(!Connect::anonymous().uses_enhanced_auth());
+        assert!(!Connect {
+            authentication: Some(Default::default()),
+            ..Connect::anonymous()
+        }
+        .uses_enhanced_auth());
+        assert!(Connect {
+            authentication: Some(Authentication {
+                method: "SCRAM-SHA-1".into(),
+                data: Default::default(),
+            }),
+            ..Connect::anonymous()
+        }
+        .uses_enhanced_auth());
+    }
+
+    #[test]
+    fn validate_will_retain() {
+        let connect = Connect {
+            will: Some(Will {
+                retain: true,
+                ..Will::with_message(Topic::from("clients/status"), "offline")
+            }),
+            ..Connect::anonymous()
+        };
+        assert!(matches!(
+            connect.validate_will_retain(false),
+            Err(Error::Reason(RetainNotSupported))
+        ));
+        assert!(connect.validate_will_retain(true).is_ok());
+        assert!(Connect::anonymous().validate_will_retain(false).is_ok());
+    }
+
+    #[test]
+    fn decoded_properties() {
+        let connect = Connect {
+            session_expiry_interval: Some(3600),
+            receive_maximum: 100,
+            topic_alias_maximum: 10,
+            request_response_information: true,
+            user_properties: vec![("Mogwaï".into(), "Cat".into())],
+            authentication: Some(Authentication {
+                method: "SCRAM-SHA-1".into(),
+                data: BinaryData::try_new(vec![0x0D, 0x15, 0xEA, 0x5E]).unwrap(),
+            }),
+            ..Connect::anonymous()
+        };
+        assert_eq!(
+            connect.decoded_properties(),
+            vec![
+                Property::SessionExpiryInterval(3600),
+                Property::ReceiveMaximum(100),
+                Property::TopicAliasMaximum(10),
+                Property::RequestResponseInformation(true),
+                Property::RequestProblemInformation(DEFAULT_REQUEST_PROBLEM_INFORMATION),
+                Property::UserProperty("Mogwaï".into(), "Cat".into()),
+                Property::AuthenticationMethod("SCRAM-SHA-1".into()),
+                Property::AuthenticationData(vec![0x0D, 0x15, 0xEA, 0x5E]),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn read_lenient_underscore() {
+        let connect = Connect {
+            clean_start: true,
+            client_id: Some("Sage_451".into()),
+            ..Default::default()
+        };
+
+        // Built by hand as write refuses such a client id
+        let mut encoded = vec![0, 4, 77, 81, 84, 84, 5, 2, 2, 88, 0, 0, 8];
+        encoded.extend_from_slice(b"Sage_451");
+        let remaining_size = encoded.len();
+
+        assert!(matches!(
+            Connect::read(
+                &mut Cursor::new(&encoded),
+                remaining_size,
+                &Default::default()
+            )
+            .await,
+            Err(Error::Reason(ClientIdentifierNotValid))
+        ));
+        let tested_result = Connect::read_lenient(&mut Cursor::new(&encoded), remaining_size)
+            .await
+            .unwrap();
+        assert_eq!(tested_result, connect);
+    }
 }
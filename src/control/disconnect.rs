@@ -1,9 +1,9 @@
 use crate::{
-    codec, PropertiesDecoder, Property,
+    codec, DecodeOptions, PacketType, PropertiesDecoder, Property,
     ReasonCode::{self, ProtocolError},
     Result as SageResult,
 };
-use std::{convert::TryInto, marker::Unpin};
+use std::marker::Unpin;
 use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
 
 /// A `Disconnect` packet can be sent by the client or the server to gracefully
@@ -75,6 +75,50 @@ impl Default for Disconnect {
 }
 
 impl Disconnect {
+    /// Whether the client asks the server to publish its will message despite
+    /// disconnecting gracefully, which is only the case with the
+    /// `DisconnectWithWillMessage` reason code.
+    pub fn should_publish_will(&self) -> bool {
+        self.reason_code == ReasonCode::DisconnectWithWillMessage
+    }
+
+    /// Whether `reason_code` can be used in a `Disconnect` packet, either by
+    /// the client or the server.
+    pub fn is_valid_reason_code(reason_code: ReasonCode) -> bool {
+        matches!(
+            reason_code,
+            ReasonCode::Success
+                | ReasonCode::DisconnectWithWillMessage
+                | ReasonCode::UnspecifiedError
+                | ReasonCode::MalformedPacket
+                | ReasonCode::ProtocolError
+                | ReasonCode::ImplementationSpecificError
+                | ReasonCode::NotAuthorized
+                | ReasonCode::ServerBusy
+                | ReasonCode::ServerShuttingDown
+                | ReasonCode::KeepAliveTimeout
+                | ReasonCode::SessionTakenOver
+                | ReasonCode::TopicFilterInvalid
+                | ReasonCode::TopicNameInvalid
+                | ReasonCode::ReceiveMaximumExceeded
+                | ReasonCode::TopicAliasInvalid
+                | ReasonCode::PacketTooLarge
+                | ReasonCode::MessageRateTooHigh
+                | ReasonCode::QuotaExceeded
+                | ReasonCode::AdministrativeAction
+                | ReasonCode::PayloadFormatInvalid
+                | ReasonCode::RetainNotSupported
+                | ReasonCode::QoSNotSupported
+                | ReasonCode::UseAnotherServer
+                | ReasonCode::ServerMoved
+                | ReasonCode::SharedSubscriptionsNotSupported
+                | ReasonCode::ConnectionRateExceeded
+                | ReasonCode::MaximumConnectTime
+                | ReasonCode::SubscriptionIdentifiersNotSupported
+                | ReasonCode::WildcardSubscriptionsNotSupported
+        )
+    }
+
     pub(crate) async fn write<W: AsyncWrite + Unpin>(self, mut writer: W) -> SageResult<usize> {
         let mut n_bytes = codec::write_reason_code(self.reason_code, &mut writer).await?;
 
@@ -101,11 +145,14 @@ impl Disconnect {
         Ok(n_bytes)
     }
 
-    pub(crate) async fn read<R: AsyncRead + Unpin>(mut reader: R) -> SageResult<Self> {
-        let reason_code = codec::read_byte(&mut reader).await?.try_into()?;
+    pub(crate) async fn read<R: AsyncRead + Unpin>(
+        mut reader: R,
+        options: &DecodeOptions,
+    ) -> SageResult<Self> {
+        let reason_code = codec::read_reason_code(&mut reader, PacketType::Disconnect).await?;
 
         let mut user_properties = Vec::new();
-        let mut properties = PropertiesDecoder::take(&mut reader).await?;
+        let mut properties = PropertiesDecoder::take(&mut reader, options).await?;
         let mut session_expiry_interval = None;
         let mut reason_string = None;
         let mut reference = None;
@@ -170,7 +217,19 @@ mod unit {
     #[tokio::test]
     async fn decode() {
         let mut test_data = Cursor::new(encoded());
-        let tested_result = Disconnect::read(&mut test_data).await.unwrap();
+        let tested_result = Disconnect::read(&mut test_data, &Default::default())
+            .await
+            .unwrap();
         assert_eq!(tested_result, decoded());
     }
+
+    #[test]
+    fn should_publish_will() {
+        assert!(!Disconnect::default().should_publish_will());
+        assert!(Disconnect {
+            reason_code: ReasonCode::DisconnectWithWillMessage,
+            ..Default::default()
+        }
+        .should_publish_will());
+    }
 }
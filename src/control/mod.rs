@@ -24,8 +24,8 @@ mod unsubscribe;
 pub type ClientID = String;
 
 pub use auth::Auth;
-pub use connack::ConnAck;
-pub use connect::Connect;
+pub use connack::{ConnAck, NegotiatedSettings};
+pub use connect::{Connect, ConnectionContext};
 pub use disconnect::Disconnect;
 pub use puback::PubAck;
 pub use pubcomp::PubComp;
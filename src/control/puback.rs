@@ -1,9 +1,9 @@
 use crate::{
-    codec, PropertiesDecoder, Property,
+    codec, DecodeOptions, PacketType, PropertiesDecoder, Property,
     ReasonCode::{self, ProtocolError},
     Result as SageResult,
 };
-use std::{convert::TryInto, marker::Unpin};
+use std::marker::Unpin;
 use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
 
 /// A `PubAck` is the response for a `Publish` message with `AtLeastOnce` as
@@ -46,6 +46,10 @@ impl Default for PubAck {
 
 impl PubAck {
     pub(crate) async fn write<W: AsyncWrite + Unpin>(self, mut writer: W) -> SageResult<usize> {
+        if self.packet_identifier == 0 {
+            return Err(ProtocolError.into());
+        }
+
         let mut n_bytes = codec::write_two_byte_integer(self.packet_identifier, &mut writer).await?;
 
         let mut properties = Vec::new();
@@ -70,8 +74,12 @@ impl PubAck {
     pub(crate) async fn read<R: AsyncRead + Unpin>(
         mut reader: R,
         shortened: bool,
+        options: &DecodeOptions,
     ) -> SageResult<Self> {
         let packet_identifier = codec::read_two_byte_integer(&mut reader).await?;
+        if packet_identifier == 0 {
+            return Err(ProtocolError.into());
+        }
 
         let mut puback = PubAck {
             packet_identifier,
@@ -81,9 +89,9 @@ impl PubAck {
         if shortened {
             puback.reason_code = ReasonCode::Success;
         } else {
-            puback.reason_code = codec::read_byte(&mut reader).await?.try_into()?;
+            puback.reason_code = codec::read_reason_code(&mut reader, PacketType::PubAck).await?;
 
-            let mut properties = PropertiesDecoder::take(&mut reader).await?;
+            let mut properties = PropertiesDecoder::take(&mut reader, options).await?;
             while properties.has_properties() {
                 match properties.read().await? {
                     Property::ReasonString(v) => puback.reason_string = Some(v),
@@ -101,6 +109,7 @@ impl PubAck {
 mod unit {
 
     use super::*;
+    use crate::Error;
     use std::io::Cursor;
 
     fn encoded() -> Vec<u8> {
@@ -131,7 +140,27 @@ mod unit {
     #[tokio::test]
     async fn decode() {
         let mut test_data = Cursor::new(encoded());
-        let tested_result = PubAck::read(&mut test_data, false).await.unwrap();
+        let tested_result = PubAck::read(&mut test_data, false, &Default::default())
+            .await
+            .unwrap();
         assert_eq!(tested_result, decoded());
     }
+
+    #[tokio::test]
+    async fn encode_zero_packet_identifier() {
+        let mut tested_result = Vec::new();
+        assert!(matches!(
+            PubAck::default().write(&mut tested_result).await,
+            Err(Error::Reason(ProtocolError))
+        ));
+    }
+
+    #[tokio::test]
+    async fn decode_zero_packet_identifier() {
+        let mut test_data = Cursor::new(vec![0, 0, 0, 0]);
+        assert!(matches!(
+            PubAck::read(&mut test_data, false, &Default::default()).await,
+            Err(Error::Reason(ProtocolError))
+        ));
+    }
 }
@@ -1,9 +1,9 @@
 use crate::{
-    codec, PropertiesDecoder, Property,
+    codec, DecodeOptions, PacketType, PropertiesDecoder, Property,
     ReasonCode::{self, ProtocolError},
     Result as SageResult,
 };
-use std::{convert::TryInto, marker::Unpin};
+use std::marker::Unpin;
 use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
 
 /// The `PubComp` packet is sent during an `ExactlyOnce` quality of service
@@ -46,6 +46,10 @@ impl Default for PubComp {
 
 impl PubComp {
     pub(crate) async fn write<W: AsyncWrite + Unpin>(self, mut writer: W) -> SageResult<usize> {
+        if self.packet_identifier == 0 {
+            return Err(ProtocolError.into());
+        }
+
         let mut n_bytes = codec::write_two_byte_integer(self.packet_identifier, &mut writer).await?;
 
         let mut properties = Vec::new();
@@ -70,8 +74,12 @@ impl PubComp {
     pub(crate) async fn read<R: AsyncRead + Unpin>(
         mut reader: R,
         shortened: bool,
+        options: &DecodeOptions,
     ) -> SageResult<Self> {
         let packet_identifier = codec::read_two_byte_integer(&mut reader).await?;
+        if packet_identifier == 0 {
+            return Err(ProtocolError.into());
+        }
 
         let mut pubcomp = PubComp {
             packet_identifier,
@@ -81,9 +89,9 @@ impl PubComp {
         if shortened {
             pubcomp.reason_code = ReasonCode::Success;
         } else {
-            pubcomp.reason_code = codec::read_byte(&mut reader).await?.try_into()?;
+            pubcomp.reason_code = codec::read_reason_code(&mut reader, PacketType::PubComp).await?;
 
-            let mut properties = PropertiesDecoder::take(&mut reader).await?;
+            let mut properties = PropertiesDecoder::take(&mut reader, options).await?;
             while properties.has_properties() {
                 match properties.read().await? {
                     Property::ReasonString(v) => pubcomp.reason_string = Some(v),
@@ -131,7 +139,9 @@ mod unit {
     #[tokio::test]
     async fn decode() {
         let mut test_data = Cursor::new(encoded());
-        let tested_result = PubComp::read(&mut test_data, false).await.unwrap();
+        let tested_result = PubComp::read(&mut test_data, false, &Default::default())
+            .await
+            .unwrap();
         assert_eq!(tested_result, decoded());
     }
 }
@@ -1,9 +1,13 @@
 use crate::{
-    codec, defaults::DEFAULT_PAYLOAD_FORMAT_INDICATOR, PropertiesDecoder, Property, QoS,
-    ReasonCode::ProtocolError, Result as SageResult, Topic,
+    codec,
+    defaults::DEFAULT_PAYLOAD_FORMAT_INDICATOR,
+    BinaryData, DecodeOptions, Error, Packet, PacketType, PropertiesDecoder, Property, PubAck,
+    PubRec, QoS,
+    ReasonCode::{self, ProtocolError, TopicNameInvalid},
+    Result as SageResult, Topic,
 };
 
-use std::marker::Unpin;
+use std::{convert::TryInto, marker::Unpin, time::Duration};
 use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 
 /// The `Publish` packet is used to send an application message to a given
@@ -48,7 +52,7 @@ pub struct Publish {
     /// If the message is part of a Request/Response communication, it can be
     /// optionnaly accompagnied with correlation data which are exchanged
     /// between the communication endpoints.
-    pub correlation_data: Option<Vec<u8>>,
+    pub correlation_data: Option<BinaryData>,
 
     /// General purpose user properties.
     pub user_properties: Vec<(String, String)>,
@@ -88,7 +92,289 @@ impl Default for Publish {
 }
 
 impl Publish {
+    /// Builds an `AtMostOnce`, non-retained publish packet with the given
+    /// topic name and message.
+    pub fn new(topic_name: impl Into<Topic>, message: impl Into<Vec<u8>>) -> Self {
+        Publish {
+            topic_name: topic_name.into(),
+            message: message.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Sets the quality of service of the message.
+    pub fn with_qos(self, qos: QoS) -> Self {
+        Publish { qos, ..self }
+    }
+
+    /// Asks the server to retain the message.
+    pub fn retained(self) -> Self {
+        Publish {
+            retain: true,
+            ..self
+        }
+    }
+
+    /// Sets the packet identifier of the message. Fails with `ProtocolError`
+    /// if the quality of service is `AtMostOnce`, which does not use packet
+    /// identifiers, or if `packet_identifier` is 0.
+    /// Therefore the quality of service must be set first.
+    pub fn with_packet_id(self, packet_identifier: u16) -> SageResult<Self> {
+        if self.qos == QoS::AtMostOnce {
+            Err(Error::Context(
+                ProtocolError,
+                "AtMostOnce publish must not have a packet identifier".into(),
+            ))
+        } else if packet_identifier == 0 {
+            Err(Error::Context(
+                ProtocolError,
+                "packet identifier must not be 0".into(),
+            ))
+        } else {
+            Ok(Publish {
+                packet_identifier: Some(packet_identifier),
+                ..self
+            })
+        }
+    }
+
+    /// Builds the copy of the packet delivered through a subscription granted
+    /// with `granted_qos`. The quality of service is lowered to `granted_qos`
+    /// if higher, the subscription identifiers are replaced by
+    /// `subscription_id` and the duplicate flag is cleared.
+    /// The copy uses `packet_id` if its quality of service is above
+    /// `AtMostOnce`, and fails with `ProtocolError` if it is missing or 0.
+    /// It is ignored otherwise.
+    /// The retain flag and topic alias are left as is, see
+    /// `SubscriptionOptions::apply_retain` and `strip_topic_alias`.
+    pub fn for_subscription(
+        &self,
+        granted_qos: QoS,
+        subscription_id: Option<u32>,
+        packet_id: Option<u16>,
+    ) -> SageResult<Publish> {
+        let publish = Publish {
+            duplicate: false,
+            qos: self.qos.min(granted_qos),
+            packet_identifier: None,
+            subscription_identifiers: subscription_id.into_iter().collect(),
+            ..self.clone()
+        };
+        match (publish.qos, packet_id) {
+            (QoS::AtMostOnce, _) => Ok(publish),
+            (_, Some(packet_id)) => publish.with_packet_id(packet_id),
+            (_, None) => Err(Error::Context(
+                ProtocolError,
+                "packet identifier is required above AtMostOnce".into(),
+            )),
+        }
+    }
+
+    /// Whether the quality of service of the message is higher than `max`,
+    /// usually the maximum quality of service of the server. Such a message
+    /// must be rejected.
+    pub fn exceeds_max_qos(&self, max: QoS) -> bool {
+        self.qos > max
+    }
+
+    /// Builds the acknowledgement of this packet with the given reason code:
+    /// nothing for `AtMostOnce`, a `PubAck` for `AtLeastOnce` and a `PubRec`
+    /// for `ExactlyOnce`. Fails with `ProtocolError` if the reason code
+    /// cannot be used in the acknowledgement or if the packet has no packet
+    /// identifier.
+    pub fn acknowledge(&self, reason: ReasonCode) -> SageResult<Option<Packet>> {
+        let packet_type = match self.qos {
+            QoS::AtMostOnce => return Ok(None),
+            QoS::AtLeastOnce => PacketType::PubAck,
+            QoS::ExactlyOnce => PacketType::PubRec,
+        };
+        let reason_code = ReasonCode::try_parse(reason.as_u8(), packet_type)?;
+        let packet_identifier = self.packet_identifier.ok_or(Error::Context(
+            ProtocolError,
+            "cannot acknowledge a PUBLISH without packet identifier".into(),
+        ))?;
+        Ok(Some(match packet_type {
+            PacketType::PubAck => PubAck {
+                packet_identifier,
+                reason_code,
+                ..Default::default()
+            }
+            .into(),
+            _ => PubRec {
+                packet_identifier,
+                reason_code,
+                ..Default::default()
+            }
+            .into(),
+        }))
+    }
+
+    /// Compares the application message of two publish packets, ignoring the
+    /// fields which legitimately differ along the delivery of a same message:
+    /// `duplicate`, `packet_identifier`, `topic_alias` and
+    /// `subscription_identifiers`, as well as the other properties.
+    /// Only the topic name, the message, the quality of service, the retain
+    /// flag, the content type and the user properties are compared.
+    pub fn payload_eq(&self, other: &Publish) -> bool {
+        self.topic_name == other.topic_name
+            && self.message == other.message
+            && self.qos == other.qos
+            && self.retain == other.retain
+            && self.content_type == other.content_type
+            && self.user_properties == other.user_properties
+    }
+
+    /// Whether this packet is the response to `request`, that is both carry
+    /// the same correlation data. Packets without correlation data are
+    /// uncorrelated and never match.
+    pub fn correlates_with(&self, request: &Publish) -> bool {
+        matches!(
+            (&self.correlation_data, &request.correlation_data),
+            (Some(lhs), Some(rhs)) if lhs == rhs
+        )
+    }
+
+    /// The size of the application message, in bytes.
+    pub fn payload_len(&self) -> usize {
+        self.message.len()
+    }
+
+    /// Copies at most the `max` first bytes of the application message,
+    /// which is convenient for logging without allocating for the whole
+    /// payload.
+    pub fn truncated_preview(&self, max: usize) -> Vec<u8> {
+        self.message[..self.message.len().min(max)].to_vec()
+    }
+
+    /// The message expiry interval as a `Duration`, if any.
+    pub fn message_expiry_duration(&self) -> Option<Duration> {
+        self.message_expiry_interval
+            .map(|v| Duration::from_secs(v as u64))
+    }
+
+    /// Sets the message expiry interval from a `Duration`. Fractions of
+    /// seconds are truncated and the value saturates at `u32::MAX` seconds.
+    pub fn set_message_expiry_duration(&mut self, duration: Option<Duration>) {
+        self.message_expiry_interval = duration.map(|d| d.as_secs().try_into().unwrap_or(u32::MAX));
+    }
+
+    /// Removes the topic alias of the packet, which is needed before
+    /// forwarding it to another connection since each connection has its own
+    /// alias space. The topic name must have been resolved beforehand, see
+    /// `TopicAliasMap::apply`, otherwise this fails with `ProtocolError` and
+    /// the packet is left unchanged.
+    pub fn strip_topic_alias(&mut self) -> SageResult<()> {
+        if self.topic_name == Topic::default() {
+            Err(Error::Context(
+                ProtocolError,
+                "cannot strip the topic alias of a PUBLISH without topic name".into(),
+            ))
+        } else {
+            self.topic_alias = None;
+            Ok(())
+        }
+    }
+
+    /// Sets the topic name of the packet and removes its topic alias, since
+    /// an explicit topic name supersedes it. Fails with `TopicNameInvalid`
+    /// if the topic name is empty or contains wildcards, in which case the
+    /// packet is left unchanged.
+    pub fn set_topic(&mut self, topic_name: impl Into<Topic>) -> SageResult<()> {
+        let topic_name = topic_name.into();
+        if topic_name == Topic::default() || topic_name.has_wildcards() {
+            Err(TopicNameInvalid.into())
+        } else {
+            self.topic_name = topic_name;
+            self.topic_alias = None;
+            Ok(())
+        }
+    }
+
+    /// Rebuilds the list of properties of the packet from its fields, in the
+    /// order they are encoded. Properties with a default value, which may be
+    /// omitted on the wire, are still listed.
+    pub fn properties(&self) -> Vec<Property> {
+        let mut properties = vec![Property::PayloadFormatIndicator(
+            self.payload_format_indicator,
+        )];
+        if let Some(v) = self.message_expiry_interval {
+            properties.push(Property::MessageExpiryInterval(v));
+        }
+        if let Some(v) = self.topic_alias {
+            properties.push(Property::TopicAlias(v));
+        }
+        if let Some(v) = &self.response_topic {
+            properties.push(Property::ResponseTopic(v.clone()));
+        }
+        if let Some(v) = &self.correlation_data {
+            properties.push(Property::CorrelationData(v.to_vec()));
+        }
+        for (k, v) in &self.user_properties {
+            properties.push(Property::UserProperty(k.clone(), v.clone()));
+        }
+        for v in &self.subscription_identifiers {
+            properties.push(Property::SubscriptionIdentifier(*v));
+        }
+        properties.push(Property::ContentType(self.content_type.clone()));
+        properties
+    }
+
+    /// Checks the `Publish` content against the rules that cannot be enforced
+    /// by types alone, returning the first violation found.
+    /// - The topic name must not be empty unless a topic alias is given
+    /// - The response topic, if any, must not be empty
+    /// - The response topic, if any, must not contain wildcards
+    pub fn validate(&self) -> SageResult<()> {
+        if self.topic_name == Topic::default() && self.topic_alias.is_none() {
+            return Err(Error::Context(
+                ProtocolError,
+                "topic name must not be empty without topic alias".into(),
+            ));
+        }
+        if let Some(response_topic) = &self.response_topic {
+            if *response_topic == Topic::default() {
+                return Err(Error::Context(
+                    ProtocolError,
+                    "response topic must not be empty".into(),
+                ));
+            }
+            if response_topic.has_wildcards() {
+                return Err(Error::Context(
+                    ProtocolError,
+                    "response topic must not contain wildcards".into(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// The number of bytes `write` would produce, computed without encoding
+    /// the packet nor copying its payload.
+    pub(crate) fn encoded_len(&self) -> SageResult<usize> {
+        self.validate()?;
+
+        let mut n_bytes = 2 + self.topic_name.to_string().len();
+        if self.qos != QoS::AtMostOnce {
+            if self.packet_identifier.is_none() {
+                return Err(ProtocolError.into());
+            }
+            n_bytes += 2;
+        }
+
+        let properties_len = self
+            .properties()
+            .iter()
+            .map(Property::encoded_len)
+            .sum::<usize>();
+        n_bytes += codec::VariableByteInteger::try_new(properties_len as u32)?.encoded_len();
+        n_bytes += properties_len;
+
+        Ok(n_bytes + self.message.len())
+    }
+
     pub(crate) async fn write<W: AsyncWrite + Unpin>(self, writer: &mut W) -> SageResult<usize> {
+        self.validate()?;
+
         let mut n_bytes = codec::write_utf8_string(&self.topic_name.to_string(), writer).await?;
 
         if self.qos != QoS::AtMostOnce {
@@ -119,7 +405,7 @@ impl Publish {
                 .await?;
         }
         if let Some(correlation_data) = self.correlation_data {
-            n_bytes += Property::CorrelationData(correlation_data)
+            n_bytes += Property::CorrelationData(correlation_data.into())
                 .encode(&mut properties)
                 .await?;
         }
@@ -149,6 +435,7 @@ impl Publish {
         qos: QoS,
         retain: bool,
         remaining_size: u64,
+        options: &DecodeOptions,
     ) -> SageResult<Self> {
         let mut reader = reader.take(remaining_size);
 
@@ -168,14 +455,14 @@ impl Publish {
         let mut subscription_identifiers = Vec::new();
         let mut content_type = Default::default();
 
-        let mut properties = PropertiesDecoder::take(&mut reader).await?;
+        let mut properties = PropertiesDecoder::take(&mut reader, options).await?;
         while properties.has_properties() {
             match properties.read().await? {
                 Property::PayloadFormatIndicator(v) => payload_format_indicator = v,
                 Property::MessageExpiryInterval(v) => message_expiry_interval = Some(v),
                 Property::TopicAlias(v) => topic_alias = Some(v),
                 Property::ResponseTopic(v) => response_topic = Some(v),
-                Property::CorrelationData(v) => correlation_data = Some(v),
+                Property::CorrelationData(v) => correlation_data = Some(BinaryData::try_new(v)?),
                 Property::UserProperty(k, v) => user_properties.push((k, v)),
                 Property::SubscriptionIdentifier(v) => subscription_identifiers.push(v),
                 Property::ContentType(v) => content_type = v,
@@ -183,6 +470,11 @@ impl Publish {
             }
         }
 
+        // Without a topic alias there is nothing to route the message on
+        if topic_name == Topic::default() && topic_alias.is_none() {
+            return Err(ProtocolError.into());
+        }
+
         let mut message = Vec::new();
         reader.read_to_end(&mut message).await?;
 
@@ -209,6 +501,7 @@ impl Publish {
 mod unit {
 
     use super::*;
+    use crate::TopicAliasMap;
     use std::io::Cursor;
 
     fn encoded() -> Vec<u8> {
@@ -234,7 +527,7 @@ mod unit {
             message_expiry_interval: Some(17),
             topic_alias: Some(451),
             response_topic: Some(Topic::from("Smells Like Teen Spirit")),
-            correlation_data: Some(vec![0x0D, 0x15, 0xEA, 0x5E]),
+            correlation_data: Some(BinaryData::try_new(vec![0x0D, 0x15, 0xEA, 0x5E]).unwrap()),
             user_properties: vec![("Mogwaï".into(), "Cat".into())],
             subscription_identifiers: vec![34, 32, 10, 11],
             content_type: "Nirvana".into(),
@@ -254,9 +547,375 @@ mod unit {
     #[tokio::test]
     async fn decode() {
         let mut test_data = Cursor::new(encoded());
-        let tested_result = Publish::read(&mut test_data, false, QoS::AtLeastOnce, true, 124)
-            .await
-            .unwrap();
+        let tested_result = Publish::read(
+            &mut test_data,
+            false,
+            QoS::AtLeastOnce,
+            true,
+            124,
+            &Default::default(),
+        )
+        .await
+        .unwrap();
         assert_eq!(tested_result, decoded());
     }
+
+    #[tokio::test]
+    async fn encode_empty_topic_without_alias() {
+        let mut tested_result = Vec::new();
+        assert!(matches!(
+            Publish::default().write(&mut tested_result).await,
+            Err(Error::Context(ProtocolError, _))
+        ));
+    }
+
+    #[tokio::test]
+    async fn encode_empty_response_topic() {
+        let test_data = Publish {
+            topic_name: Topic::from("sensors/temperature"),
+            response_topic: Some(Topic::from("")),
+            ..Default::default()
+        };
+        let mut tested_result = Vec::new();
+        assert!(matches!(
+            test_data.write(&mut tested_result).await,
+            Err(Error::Context(ProtocolError, message)) if message == "response topic must not be empty"
+        ));
+    }
+
+    #[tokio::test]
+    async fn encode_wildcard_response_topic() {
+        let test_data = Publish {
+            topic_name: Topic::from("sensors/temperature"),
+            response_topic: Some(Topic::from("sensors/+/temperature")),
+            ..Default::default()
+        };
+        let mut tested_result = Vec::new();
+        assert!(matches!(
+            test_data.write(&mut tested_result).await,
+            Err(Error::Context(ProtocolError, message)) if message == "response topic must not contain wildcards"
+        ));
+    }
+
+    #[tokio::test]
+    async fn retained_at_least_once() {
+        let test_data = Publish::new("sensors/temperature", "21.5")
+            .with_qos(QoS::AtLeastOnce)
+            .retained()
+            .with_packet_id(1337)
+            .unwrap();
+        let mut encoded = Vec::new();
+        let n_bytes = test_data.clone().write(&mut encoded).await.unwrap();
+        let mut test_stream = Cursor::new(encoded);
+        let tested_result = Publish::read(
+            &mut test_stream,
+            false,
+            QoS::AtLeastOnce,
+            true,
+            n_bytes as u64,
+            &Default::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(tested_result, test_data);
+        assert!(tested_result.retain);
+    }
+
+    #[test]
+    fn message_expiry_duration() {
+        let mut publish = Publish::default();
+        publish.set_message_expiry_duration(Some(Duration::from_secs(600)));
+        assert_eq!(publish.message_expiry_interval, Some(600));
+        assert_eq!(
+            publish.message_expiry_duration(),
+            Some(Duration::from_secs(600))
+        );
+        publish.set_message_expiry_duration(None);
+        assert_eq!(publish.message_expiry_duration(), None);
+    }
+
+    #[tokio::test]
+    async fn decode_empty_topic_without_alias() {
+        let mut test_data = Cursor::new(vec![0, 0, 0]);
+        assert!(matches!(
+            Publish::read(
+                &mut test_data,
+                false,
+                QoS::AtMostOnce,
+                false,
+                3,
+                &Default::default()
+            )
+            .await,
+            Err(Error::Reason(ProtocolError))
+        ));
+    }
+
+    #[tokio::test]
+    async fn decode_empty_topic_with_alias() {
+        let mut test_data = Cursor::new(vec![0, 0, 3, 0x23, 0, 5]);
+        let tested_result = Publish::read(
+            &mut test_data,
+            false,
+            QoS::AtMostOnce,
+            false,
+            6,
+            &Default::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(tested_result.topic_name, Topic::default());
+        assert_eq!(tested_result.topic_alias, Some(5));
+    }
+
+    #[test]
+    fn properties() {
+        assert_eq!(
+            decoded().properties(),
+            vec![
+                Property::PayloadFormatIndicator(true),
+                Property::MessageExpiryInterval(17),
+                Property::TopicAlias(451),
+                Property::ResponseTopic(Topic::from("Smells Like Teen Spirit")),
+                Property::CorrelationData(vec![0x0D, 0x15, 0xEA, 0x5E]),
+                Property::UserProperty("Mogwaï".into(), "Cat".into()),
+                Property::SubscriptionIdentifier(34),
+                Property::SubscriptionIdentifier(32),
+                Property::SubscriptionIdentifier(10),
+                Property::SubscriptionIdentifier(11),
+                Property::ContentType("Nirvana".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn exceeds_max_qos() {
+        let publish = Publish {
+            qos: QoS::ExactlyOnce,
+            ..Default::default()
+        };
+        assert!(publish.exceeds_max_qos(QoS::AtLeastOnce));
+        assert!(!publish.exceeds_max_qos(QoS::ExactlyOnce));
+    }
+
+    #[test]
+    fn packet_id_at_most_once() {
+        assert!(matches!(
+            Publish::new("sensors/temperature", "21.5").with_packet_id(1337),
+            Err(Error::Context(ProtocolError, _))
+        ));
+    }
+
+    #[test]
+    fn packet_id_zero() {
+        assert!(matches!(
+            Publish::new("sensors/temperature", "21.5")
+                .with_qos(QoS::ExactlyOnce)
+                .with_packet_id(0),
+            Err(Error::Context(ProtocolError, _))
+        ));
+    }
+
+    #[test]
+    fn truncated_preview_short() {
+        let publish = Publish::new("sensors/temperature", "21.5");
+        assert_eq!(publish.payload_len(), 4);
+        assert_eq!(publish.truncated_preview(16), b"21.5".to_vec());
+    }
+
+    #[test]
+    fn truncated_preview_long() {
+        let publish = Publish::new("sensors/temperature", vec![0x2A; 4096]);
+        assert_eq!(publish.payload_len(), 4096);
+        assert_eq!(publish.truncated_preview(16), vec![0x2A; 16]);
+    }
+
+    #[test]
+    fn acknowledge_at_most_once() {
+        let publish = Publish::new("sensors/temperature", "21.5");
+        assert!(publish.acknowledge(ReasonCode::Success).unwrap().is_none());
+    }
+
+    #[test]
+    fn acknowledge_at_least_once() {
+        let publish = Publish::new("sensors/temperature", "21.5")
+            .with_qos(QoS::AtLeastOnce)
+            .with_packet_id(1337)
+            .unwrap();
+        assert!(matches!(
+            publish.acknowledge(ReasonCode::NoMatchingSubscribers),
+            Ok(Some(Packet::PubAck(PubAck {
+                packet_identifier: 1337,
+                reason_code: ReasonCode::NoMatchingSubscribers,
+                ..
+            })))
+        ));
+        assert!(matches!(
+            publish.acknowledge(ReasonCode::PacketIdentifierNotFound),
+            Err(Error::Reason(ProtocolError))
+        ));
+    }
+
+    #[test]
+    fn acknowledge_exactly_once() {
+        let publish = Publish::new("sensors/temperature", "21.5")
+            .with_qos(QoS::ExactlyOnce)
+            .with_packet_id(1337)
+            .unwrap();
+        assert!(matches!(
+            publish.acknowledge(ReasonCode::Success),
+            Ok(Some(Packet::PubRec(PubRec {
+                packet_identifier: 1337,
+                reason_code: ReasonCode::Success,
+                ..
+            })))
+        ));
+    }
+
+    #[test]
+    fn strip_topic_alias() {
+        let mut publish = Publish {
+            topic_alias: Some(1),
+            ..Publish::new(Topic::default(), "21.5")
+        };
+        assert!(matches!(
+            publish.strip_topic_alias(),
+            Err(Error::Context(ProtocolError, _))
+        ));
+        assert_eq!(publish.topic_alias, Some(1));
+
+        let mut aliases = TopicAliasMap::new(10);
+        aliases
+            .register(1, Topic::from("sensors/temperature"))
+            .unwrap();
+        aliases.apply(&mut publish).unwrap();
+        publish.strip_topic_alias().unwrap();
+        assert_eq!(publish.topic_alias, None);
+        assert_eq!(publish.topic_name, Topic::from("sensors/temperature"));
+    }
+
+    #[test]
+    fn set_topic() {
+        let mut publish = Publish {
+            topic_alias: Some(1),
+            ..Publish::new("sensors/temperature", "21.5")
+        };
+        publish.set_topic("sensors/humidity").unwrap();
+        assert_eq!(publish.topic_name, Topic::from("sensors/humidity"));
+        assert_eq!(publish.topic_alias, None);
+
+        assert!(matches!(
+            publish.set_topic("sensors/+"),
+            Err(Error::Reason(TopicNameInvalid))
+        ));
+        assert_eq!(publish.topic_name, Topic::from("sensors/humidity"));
+    }
+
+    #[test]
+    fn payload_eq() {
+        let sent = Publish::new("sensors/temperature", "21.5")
+            .with_qos(QoS::AtLeastOnce)
+            .with_packet_id(1337)
+            .unwrap();
+        let received = Publish {
+            packet_identifier: Some(42),
+            subscription_identifiers: vec![3],
+            ..sent.clone()
+        };
+        assert_ne!(sent, received);
+        assert!(sent.payload_eq(&received));
+
+        let other = Publish {
+            message: b"22.0".to_vec(),
+            ..sent.clone()
+        };
+        assert!(!sent.payload_eq(&other));
+    }
+
+    #[test]
+    fn correlates_with() {
+        let request = Publish {
+            response_topic: Some(Topic::from("responses/temperature")),
+            correlation_data: Some(BinaryData::try_new(vec![0x0D, 0x15, 0xEA, 0x5E]).unwrap()),
+            ..Publish::new("requests/temperature", "")
+        };
+        let response = Publish {
+            correlation_data: request.correlation_data.clone(),
+            ..Publish::new("responses/temperature", "21.5")
+        };
+        assert!(response.correlates_with(&request));
+
+        let other = Publish {
+            correlation_data: Some(BinaryData::try_new(vec![0x2A]).unwrap()),
+            ..response.clone()
+        };
+        assert!(!other.correlates_with(&request));
+
+        let uncorrelated = Publish::new("responses/temperature", "21.5");
+        assert!(!uncorrelated.correlates_with(&uncorrelated.clone()));
+        assert!(!uncorrelated.correlates_with(&request));
+    }
+
+    #[tokio::test]
+    async fn empty_message_with_topic_alias() {
+        let publish = Publish {
+            qos: QoS::AtLeastOnce,
+            packet_identifier: Some(1337),
+            topic_alias: Some(3),
+            ..Publish::new(Topic::default(), "")
+        };
+
+        let mut encoded = Vec::new();
+        let n_bytes = publish.clone().write(&mut encoded).await.unwrap();
+        assert_eq!(n_bytes, encoded.len());
+        assert_eq!(publish.encoded_len().unwrap(), n_bytes);
+
+        // The bytes following the packet must not be read as its message
+        encoded.extend_from_slice(&[0xC0, 0x00]);
+        let mut test_data = Cursor::new(encoded);
+        let tested_result = Publish::read(
+            &mut test_data,
+            false,
+            QoS::AtLeastOnce,
+            false,
+            n_bytes as u64,
+            &Default::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(tested_result, publish);
+        assert_eq!(test_data.position(), n_bytes as u64);
+    }
+
+    #[test]
+    fn for_subscription() {
+        let publish = Publish {
+            duplicate: true,
+            packet_identifier: Some(1337),
+            subscription_identifiers: vec![1],
+            ..Publish::new("sensors/temperature", "21.5").with_qos(QoS::ExactlyOnce)
+        };
+
+        let forwarded = publish
+            .for_subscription(QoS::AtLeastOnce, Some(42), Some(7))
+            .unwrap();
+        assert_eq!(forwarded.qos, QoS::AtLeastOnce);
+        assert_eq!(forwarded.packet_identifier, Some(7));
+        assert_eq!(forwarded.subscription_identifiers, vec![42]);
+        assert!(!forwarded.duplicate);
+        assert_eq!(forwarded.topic_name, publish.topic_name);
+        assert_eq!(forwarded.message, publish.message);
+
+        assert!(matches!(
+            publish.for_subscription(QoS::AtLeastOnce, None, None),
+            Err(Error::Context(ProtocolError, _))
+        ));
+
+        let forwarded = publish
+            .for_subscription(QoS::AtMostOnce, None, Some(7))
+            .unwrap();
+        assert_eq!(forwarded.qos, QoS::AtMostOnce);
+        assert_eq!(forwarded.packet_identifier, None);
+        assert!(forwarded.subscription_identifiers.is_empty());
+    }
 }
@@ -1,13 +1,14 @@
 use crate::{
-    codec, PropertiesDecoder, Property,
+    codec, DecodeOptions, PacketType, PropertiesDecoder, Property,
     ReasonCode::{self, ProtocolError},
     Result as SageResult,
 };
-use std::{convert::TryInto, marker::Unpin};
+use std::marker::Unpin;
 use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
 
 /// The `PubRec` packet is sent during an `ExactlyOnce` quality of service
-/// publish.
+/// publish. The sender of the `Publish` packet must answer it with a `PubRel`
+/// packet, which `Qos2State` can enforce.
 #[derive(Debug, PartialEq, Clone)]
 pub struct PubRec {
     /// The packet identifier is used to identify the message throughout the
@@ -46,6 +47,10 @@ impl Default for PubRec {
 
 impl PubRec {
     pub(crate) async fn write<W: AsyncWrite + Unpin>(self, mut writer: W) -> SageResult<usize> {
+        if self.packet_identifier == 0 {
+            return Err(ProtocolError.into());
+        }
+
         let mut n_bytes = codec::write_two_byte_integer(self.packet_identifier, &mut writer).await?;
 
         let mut properties = Vec::new();
@@ -70,8 +75,12 @@ impl PubRec {
     pub(crate) async fn read<R: AsyncRead + Unpin>(
         mut reader: R,
         shortened: bool,
+        options: &DecodeOptions,
     ) -> SageResult<Self> {
         let packet_identifier = codec::read_two_byte_integer(&mut reader).await?;
+        if packet_identifier == 0 {
+            return Err(ProtocolError.into());
+        }
 
         let mut pubrec = PubRec {
             packet_identifier,
@@ -81,9 +90,9 @@ impl PubRec {
         if shortened {
             pubrec.reason_code = ReasonCode::Success;
         } else {
-            pubrec.reason_code = codec::read_byte(&mut reader).await?.try_into()?;
+            pubrec.reason_code = codec::read_reason_code(&mut reader, PacketType::PubRec).await?;
 
-            let mut properties = PropertiesDecoder::take(&mut reader).await?;
+            let mut properties = PropertiesDecoder::take(&mut reader, options).await?;
             while properties.has_properties() {
                 match properties.read().await? {
                     Property::ReasonString(v) => pubrec.reason_string = Some(v),
@@ -100,6 +109,7 @@ impl PubRec {
 #[cfg(test)]
 mod unit {
     use super::*;
+    use crate::Error;
     use std::io::Cursor;
 
     fn encoded() -> Vec<u8> {
@@ -130,7 +140,28 @@ mod unit {
     #[tokio::test]
     async fn decode() {
         let mut test_data = Cursor::new(encoded());
-        let tested_result = PubRec::read(&mut test_data, false).await.unwrap();
+        let tested_result = PubRec::read(&mut test_data, false, &Default::default())
+            .await
+            .unwrap();
         assert_eq!(tested_result, decoded());
     }
+
+    #[tokio::test]
+    async fn decode_no_matching_subscribers() {
+        let mut test_data = Cursor::new(vec![5, 57, 0x10, 0]);
+        let tested_result = PubRec::read(&mut test_data, false, &Default::default())
+            .await
+            .unwrap();
+        assert_eq!(tested_result.reason_code, ReasonCode::NoMatchingSubscribers);
+    }
+
+    #[tokio::test]
+    async fn decode_invalid_reason_code() {
+        // PacketIdentifierNotFound is only valid in PUBREL and PUBCOMP
+        let mut test_data = Cursor::new(vec![5, 57, 0x92, 0]);
+        assert!(matches!(
+            PubRec::read(&mut test_data, false, &Default::default()).await,
+            Err(Error::Reason(ProtocolError))
+        ));
+    }
 }
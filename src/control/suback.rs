@@ -1,9 +1,9 @@
 use crate::{
-    codec, PropertiesDecoder, Property,
-    ReasonCode::{self, ProtocolError},
+    codec, DecodeOptions, PacketType, PropertiesDecoder, Property,
+    ReasonCode::{self, MalformedPacket, ProtocolError},
     Result as SageResult,
 };
-use std::{convert::TryInto, marker::Unpin};
+use std::marker::Unpin;
 use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 
 /// The `SubAck` packet is sent by a server to confirm a `Subscribe` has been
@@ -35,6 +35,10 @@ impl Default for SubAck {
 
 impl SubAck {
     pub(crate) async fn write<W: AsyncWrite + Unpin>(self, mut writer: W) -> SageResult<usize> {
+        if self.reason_codes.is_empty() {
+            return Err(ProtocolError.into());
+        }
+
         let mut n_bytes = codec::write_two_byte_integer(self.packet_identifier, &mut writer).await?;
 
         let mut properties = Vec::new();
@@ -56,12 +60,13 @@ impl SubAck {
     pub(crate) async fn read<R: AsyncRead + Unpin>(
         reader: R,
         remaining_size: usize,
+        options: &DecodeOptions,
     ) -> SageResult<Self> {
         let mut reader = reader.take(remaining_size as u64);
 
         let packet_identifier = codec::read_two_byte_integer(&mut reader).await?;
         let mut user_properties = Vec::new();
-        let mut properties = PropertiesDecoder::take(&mut reader).await?;
+        let mut properties = PropertiesDecoder::take(&mut reader, options).await?;
         while properties.has_properties() {
             match properties.read().await? {
                 Property::UserProperty(k, v) => user_properties.push((k, v)),
@@ -69,10 +74,15 @@ impl SubAck {
             }
         }
 
+        // A SubAck answers at least one subscription
+        if reader.limit() == 0 {
+            return Err(MalformedPacket.into());
+        }
+
         let mut reason_codes = Vec::new();
 
         while reader.limit() > 0 {
-            reason_codes.push(codec::read_byte(&mut reader).await?.try_into()?);
+            reason_codes.push(codec::read_reason_code(&mut reader, PacketType::SubAck).await?);
         }
 
         Ok(SubAck {
@@ -86,6 +96,7 @@ impl SubAck {
 #[cfg(test)]
 mod unit {
     use super::*;
+    use crate::Error;
     use std::io::Cursor;
 
     fn encoded() -> Vec<u8> {
@@ -117,7 +128,32 @@ mod unit {
     #[tokio::test]
     async fn decode() {
         let mut test_data = Cursor::new(encoded());
-        let tested_result = SubAck::read(&mut test_data, 20).await.unwrap();
+        let tested_result = SubAck::read(&mut test_data, 20, &Default::default())
+            .await
+            .unwrap();
         assert_eq!(tested_result, decoded());
     }
+
+    #[tokio::test]
+    async fn encode_without_reason_codes() {
+        let test_data = SubAck {
+            packet_identifier: 1337,
+            ..Default::default()
+        };
+        let mut tested_result = Vec::new();
+        assert!(matches!(
+            test_data.write(&mut tested_result).await,
+            Err(Error::Reason(ProtocolError))
+        ));
+    }
+
+    #[tokio::test]
+    async fn decode_without_reason_codes() {
+        // Packet identifier and an empty property block only
+        let mut test_data = Cursor::new(vec![5, 57, 0]);
+        assert!(matches!(
+            SubAck::read(&mut test_data, 3, &Default::default()).await,
+            Err(Error::Reason(MalformedPacket))
+        ));
+    }
 }
@@ -1,7 +1,10 @@
 use crate::{
-    codec, Error, PropertiesDecoder, Property, QoS,
-    ReasonCode::{MalformedPacket, ProtocolError},
-    Result as SageResult, Topic,
+    codec, ConnAck, DecodeOptions, Error, PropertiesDecoder, Property, QoS,
+    ReasonCode::{
+        MalformedPacket, ProtocolError, SharedSubscriptionsNotSupported,
+        SubscriptionIdentifiersNotSupported, WildcardSubscriptionsNotSupported,
+    },
+    Result as SageResult, Topic, UnSubscribe,
 };
 use std::{
     convert::{TryFrom, TryInto},
@@ -66,6 +69,28 @@ impl Default for SubscriptionOptions {
 }
 
 impl SubscriptionOptions {
+    /// Lowers the quality of service of the subscription to `max` if it is
+    /// higher. A server must grant subscriptions at its own maximum quality of
+    /// service instead of rejecting them.
+    pub fn clamp_qos(&mut self, max: QoS) {
+        self.qos = self.qos.min(max);
+    }
+
+    /// Whether a message must be forwarded through this subscription.
+    /// `publisher_is_subscriber` tells whether the message was published by
+    /// the client holding the subscription, which must not receive it back
+    /// if `no_local` is set.
+    pub fn should_forward(&self, publisher_is_subscriber: bool) -> bool {
+        !(self.no_local && publisher_is_subscriber)
+    }
+
+    /// The retain flag of a message forwarded through this subscription,
+    /// given the flag `original_retain` it was published with. The flag is
+    /// only kept if `retain_as_published` is set, and cleared otherwise.
+    pub fn apply_retain(&self, original_retain: bool) -> bool {
+        self.retain_as_published && original_retain
+    }
+
     async fn encode<W: AsyncWrite + Unpin>(self, writer: &mut W) -> SageResult<usize> {
         let byte: u8 = self.qos as u8
             | (self.no_local as u8) << 2
@@ -112,11 +137,110 @@ pub struct Subscribe {
 }
 
 impl Subscribe {
+    /// Builds a `Subscribe` packet out of any iterator of topic filters and
+    /// their options.
+    /// A `Subscribe` packet must contain at least one subscription, so an
+    /// empty iterator results in a `ProtocolError`.
+    pub fn from_subscriptions<I>(packet_identifier: u16, subscriptions: I) -> SageResult<Self>
+    where
+        I: IntoIterator<Item = (Topic, SubscriptionOptions)>,
+    {
+        let subscriptions: Vec<_> = subscriptions.into_iter().collect();
+        if subscriptions.is_empty() {
+            Err(ProtocolError.into())
+        } else {
+            Ok(Subscribe {
+                packet_identifier,
+                subscriptions,
+                ..Default::default()
+            })
+        }
+    }
+
+    /// The subscription identifier the server must include in the `Publish`
+    /// packets it delivers through any of these subscriptions, if any.
+    pub fn subscription_id_for_delivery(&self) -> Option<u32> {
+        self.subscription_identifier
+    }
+
+    /// Iterates over the topic filters of the packet, each with the quality
+    /// of service requested for it, in the order of the subscriptions.
+    pub fn filters(&self) -> impl Iterator<Item = (&Topic, QoS)> {
+        self.subscriptions
+            .iter()
+            .map(|(topic, options)| (topic, options.qos))
+    }
+
+    /// Builds the `UnSubscribe` packet removing all the subscriptions of this
+    /// packet, with the topic filters in the same order.
+    pub fn to_unsubscribe(&self, packet_identifier: u16) -> UnSubscribe {
+        UnSubscribe {
+            packet_identifier,
+            subscriptions: self
+                .subscriptions
+                .iter()
+                .map(|(topic, _)| topic.to_string())
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Checks the packet only uses features the server declared available in
+    /// its `ConnAck`: shared subscriptions, wildcard subscriptions and
+    /// subscription identifiers. The reason code of the error is the one the
+    /// server would answer with.
+    pub fn validate_against_connack(&self, connack: &ConnAck) -> SageResult<()> {
+        if !connack.subscription_identifiers_available && self.subscription_identifier.is_some() {
+            return Err(SubscriptionIdentifiersNotSupported.into());
+        }
+        for (topic, _) in &self.subscriptions {
+            if !connack.shared_subscription_available && topic.share().is_some() {
+                return Err(SharedSubscriptionsNotSupported.into());
+            }
+            if !connack.wildcard_subscription_available && topic.has_wildcards() {
+                return Err(WildcardSubscriptionsNotSupported.into());
+            }
+        }
+        Ok(())
+    }
+
+    /// The number of bytes `write` produces, computed from the packet fields.
+    /// This is linear in the number of subscriptions.
+    /// Fails like `write` if the subscription identifier is 0 or exceeds the
+    /// maximum of a variable byte integer.
+    pub(crate) fn encoded_len(&self) -> SageResult<usize> {
+        let subscription_identifier_len = match self.subscription_identifier {
+            Some(0) => return Err(ProtocolError.into()),
+            Some(v) => {
+                codec::VariableByteInteger::try_new(v)?;
+                Property::SubscriptionIdentifier(v).encoded_len()
+            }
+            None => 0,
+        };
+        let properties_len = subscription_identifier_len
+            + self
+                .user_properties
+                .iter()
+                .map(|(k, v)| 5 + k.len() + v.len())
+                .sum::<usize>();
+        let properties_len_len =
+            codec::VariableByteInteger::try_new(properties_len as u32)?.encoded_len();
+        let payload_len = self
+            .subscriptions
+            .iter()
+            .map(|(topic, _)| 2 + topic.to_string().len() + 1)
+            .sum::<usize>();
+        Ok(2 + properties_len_len + properties_len + payload_len)
+    }
+
+    /// Writes the packet, each subscription being written once. When encoded
+    /// through `Packet`, the output buffer is sized upfront from
+    /// `encoded_len`, so that writing is linear in the number of
+    /// subscriptions, without reallocations.
     pub(crate) async fn write<W: AsyncWrite + Unpin>(self, writer: &mut W) -> SageResult<usize> {
         let mut n_bytes = codec::write_two_byte_integer(self.packet_identifier, writer).await?;
 
         let mut properties = Vec::new();
-
         if let Some(v) = self.subscription_identifier {
             n_bytes += Property::SubscriptionIdentifier(v)
                 .encode(&mut properties)
@@ -129,9 +253,9 @@ impl Subscribe {
         n_bytes += codec::write_variable_byte_integer(properties.len() as u32, writer).await?;
         writer.write_all(&properties).await?;
 
-        for option in self.subscriptions {
-            n_bytes += codec::write_utf8_string(&option.0.to_string(), writer).await?;
-            n_bytes += option.1.encode(writer).await?;
+        for (topic, options) in self.subscriptions {
+            n_bytes += codec::write_utf8_string(&topic.to_string(), writer).await?;
+            n_bytes += options.encode(writer).await?;
         }
 
         Ok(n_bytes)
@@ -140,6 +264,7 @@ impl Subscribe {
     pub(crate) async fn read<R: AsyncRead + Unpin>(
         reader: R,
         remaining_size: usize,
+        options: &DecodeOptions,
     ) -> SageResult<Self> {
         let mut reader = reader.take(remaining_size as u64);
         let packet_identifier = codec::read_two_byte_integer(&mut reader).await?;
@@ -147,7 +272,7 @@ impl Subscribe {
         let mut user_properties = Vec::new();
         let mut subscription_identifier = None;
 
-        let mut properties = PropertiesDecoder::take(&mut reader).await?;
+        let mut properties = PropertiesDecoder::take(&mut reader, options).await?;
         while properties.has_properties() {
             match properties.read().await? {
                 Property::SubscriptionIdentifier(v) => subscription_identifier = Some(v),
@@ -265,7 +390,257 @@ mod unit {
     #[tokio::test]
     async fn decode() {
         let mut test_data = Cursor::new(encoded());
-        let tested_result = Subscribe::read(&mut test_data, 59).await.unwrap();
+        let tested_result = Subscribe::read(&mut test_data, 59, &Default::default())
+            .await
+            .unwrap();
         assert_eq!(tested_result, decoded());
     }
+
+    #[test]
+    fn from_subscriptions() {
+        let subscribe = Subscribe::from_subscriptions(
+            1337,
+            ["harder", "better"].iter().map(|&filter| {
+                (
+                    Topic::from(filter),
+                    SubscriptionOptions {
+                        qos: QoS::AtLeastOnce,
+                        ..Default::default()
+                    },
+                )
+            }),
+        )
+        .unwrap();
+
+        assert_eq!(subscribe.packet_identifier, 1337);
+        assert_eq!(
+            subscribe.subscriptions,
+            vec![
+                (
+                    Topic::from("harder"),
+                    SubscriptionOptions {
+                        qos: QoS::AtLeastOnce,
+                        ..Default::default()
+                    }
+                ),
+                (
+                    Topic::from("better"),
+                    SubscriptionOptions {
+                        qos: QoS::AtLeastOnce,
+                        ..Default::default()
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn from_subscriptions_empty() {
+        assert!(matches!(
+            Subscribe::from_subscriptions(1337, std::iter::empty()),
+            Err(Error::Reason(ProtocolError))
+        ));
+    }
+
+    #[test]
+    fn clamp_qos() {
+        let mut options = SubscriptionOptions {
+            qos: QoS::ExactlyOnce,
+            ..Default::default()
+        };
+        options.clamp_qos(QoS::AtLeastOnce);
+        assert_eq!(options.qos, QoS::AtLeastOnce);
+        options.clamp_qos(QoS::ExactlyOnce);
+        assert_eq!(options.qos, QoS::AtLeastOnce);
+    }
+
+    #[test]
+    fn should_forward() {
+        let local = SubscriptionOptions::default();
+        assert!(local.should_forward(false));
+        assert!(local.should_forward(true));
+
+        let no_local = SubscriptionOptions {
+            no_local: true,
+            ..Default::default()
+        };
+        assert!(no_local.should_forward(false));
+        assert!(!no_local.should_forward(true));
+    }
+
+    #[test]
+    fn apply_retain() {
+        assert!(!SubscriptionOptions::default().apply_retain(true));
+
+        let retain_as_published = SubscriptionOptions {
+            retain_as_published: true,
+            ..Default::default()
+        };
+        assert!(retain_as_published.apply_retain(true));
+        assert!(!retain_as_published.apply_retain(false));
+    }
+
+    #[test]
+    fn subscription_id_for_delivery() {
+        assert_eq!(decoded().subscription_id_for_delivery(), Some(451));
+        assert_eq!(Subscribe::default().subscription_id_for_delivery(), None);
+    }
+
+    #[tokio::test]
+    async fn many_subscriptions() {
+        let subscribe = Subscribe::from_subscriptions(
+            1337,
+            (0..5000).map(|i| (Topic::from(format!("sage/{}", i)), Default::default())),
+        )
+        .unwrap();
+        let mut encoded = Vec::new();
+        let n_bytes = subscribe.clone().write(&mut encoded).await.unwrap();
+        assert_eq!(n_bytes, encoded.len());
+        assert_eq!(subscribe.encoded_len().unwrap(), n_bytes);
+        let mut test_data = Cursor::new(encoded);
+        let tested_result = Subscribe::read(&mut test_data, n_bytes, &Default::default())
+            .await
+            .unwrap();
+        assert_eq!(tested_result, subscribe);
+    }
+
+    #[test]
+    fn to_unsubscribe() {
+        let subscribe = Subscribe::from_subscriptions(
+            1337,
+            vec![
+                (Topic::from("sensors/+/temperature"), Default::default()),
+                (Topic::from("$share/group/sensors/#"), Default::default()),
+            ],
+        )
+        .unwrap();
+        let unsubscribe = subscribe.to_unsubscribe(1338);
+        assert_eq!(unsubscribe.packet_identifier, 1338);
+        assert_eq!(
+            unsubscribe.subscriptions,
+            vec![
+                String::from("sensors/+/temperature"),
+                String::from("$share/group/sensors/#")
+            ]
+        );
+    }
+
+    #[test]
+    fn validate_against_connack() {
+        let subscribe = Subscribe {
+            subscription_identifier: Some(42),
+            ..Subscribe::from_subscriptions(
+                1337,
+                vec![
+                    (Topic::from("sensors/+/temperature"), Default::default()),
+                    (Topic::from("$share/group/sensors"), Default::default()),
+                ],
+            )
+            .unwrap()
+        };
+        assert!(subscribe
+            .validate_against_connack(&ConnAck::default())
+            .is_ok());
+
+        let connack = ConnAck {
+            shared_subscription_available: false,
+            ..Default::default()
+        };
+        assert!(matches!(
+            subscribe.validate_against_connack(&connack),
+            Err(Error::Reason(SharedSubscriptionsNotSupported))
+        ));
+
+        let connack = ConnAck {
+            wildcard_subscription_available: false,
+            ..Default::default()
+        };
+        assert!(matches!(
+            subscribe.validate_against_connack(&connack),
+            Err(Error::Reason(WildcardSubscriptionsNotSupported))
+        ));
+
+        let connack = ConnAck {
+            subscription_identifiers_available: false,
+            ..Default::default()
+        };
+        assert!(matches!(
+            subscribe.validate_against_connack(&connack),
+            Err(Error::Reason(SubscriptionIdentifiersNotSupported))
+        ));
+    }
+
+    #[test]
+    fn filters() {
+        let subscribe = Subscribe::from_subscriptions(
+            1337,
+            vec![
+                (
+                    Topic::from("sensors/+/temperature"),
+                    SubscriptionOptions {
+                        qos: QoS::ExactlyOnce,
+                        ..Default::default()
+                    },
+                ),
+                (Topic::from("sensors/#"), Default::default()),
+            ],
+        )
+        .unwrap();
+        let filters: Vec<_> = subscribe.filters().collect();
+        assert_eq!(
+            filters,
+            vec![
+                (&Topic::from("sensors/+/temperature"), QoS::ExactlyOnce),
+                (&Topic::from("sensors/#"), QoS::AtMostOnce),
+            ]
+        );
+    }
+
+    #[test]
+    fn validate_against_connack_subscription_identifier() {
+        let connack = ConnAck {
+            subscription_identifiers_available: false,
+            ..Default::default()
+        };
+        let subscribe = Subscribe::from_subscriptions(
+            1337,
+            vec![(Topic::from("sensors/temperature"), Default::default())],
+        )
+        .unwrap();
+        assert!(subscribe.validate_against_connack(&connack).is_ok());
+
+        let subscribe = Subscribe {
+            subscription_identifier: Some(42),
+            ..subscribe
+        };
+        assert!(matches!(
+            subscribe.validate_against_connack(&connack),
+            Err(Error::Reason(SubscriptionIdentifiersNotSupported))
+        ));
+    }
+
+    #[test]
+    fn encoded_len_invalid_subscription_identifier() {
+        let subscribe = Subscribe {
+            subscription_identifier: Some(0),
+            ..Subscribe::from_subscriptions(
+                1337,
+                vec![(Topic::from("sensors/temperature"), Default::default())],
+            )
+            .unwrap()
+        };
+        assert!(matches!(
+            subscribe.encoded_len(),
+            Err(Error::Reason(ProtocolError))
+        ));
+
+        let subscribe = Subscribe {
+            subscription_identifier: Some(codec::VariableByteInteger::MAX + 1),
+            ..subscribe
+        };
+        assert!(matches!(
+            subscribe.encoded_len(),
+            Err(Error::Context(MalformedPacket, _))
+        ));
+    }
 }
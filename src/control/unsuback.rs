@@ -1,9 +1,9 @@
 use crate::{
-    codec, PropertiesDecoder, Property,
+    codec, DecodeOptions, PacketType, PropertiesDecoder, Property,
     ReasonCode::{self, ProtocolError},
     Result as SageResult,
 };
-use std::{convert::TryInto, marker::Unpin};
+use std::marker::Unpin;
 use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 
 /// An `UnSubAck` is sent by the server to acknowledge an unsubscribe request.
@@ -64,12 +64,13 @@ impl UnSubAck {
     pub(crate) async fn read<R: AsyncRead + Unpin>(
         reader: R,
         remaining_size: usize,
+        options: &DecodeOptions,
     ) -> SageResult<Self> {
         let mut reader = reader.take(remaining_size as u64);
 
         let packet_identifier = codec::read_two_byte_integer(&mut reader).await?;
         let mut user_properties = Vec::new();
-        let mut properties = PropertiesDecoder::take(&mut reader).await?;
+        let mut properties = PropertiesDecoder::take(&mut reader, options).await?;
         let mut reason_string = None;
         while properties.has_properties() {
             match properties.read().await? {
@@ -82,7 +83,7 @@ impl UnSubAck {
         let mut reason_codes = Vec::new();
 
         while reader.limit() > 0 {
-            reason_codes.push(codec::read_byte(&mut reader).await?.try_into()?);
+            reason_codes.push(codec::read_reason_code(&mut reader, PacketType::UnSubAck).await?);
         }
 
         Ok(UnSubAck {
@@ -97,6 +98,7 @@ impl UnSubAck {
 #[cfg(test)]
 mod unit {
     use super::*;
+    use crate::Error;
     use std::io::Cursor;
 
     fn encoded() -> Vec<u8> {
@@ -131,7 +133,31 @@ mod unit {
     #[tokio::test]
     async fn decode() {
         let mut test_data = Cursor::new(encoded());
-        let tested_result = UnSubAck::read(&mut test_data, 41).await.unwrap();
+        let tested_result = UnSubAck::read(&mut test_data, 41, &Default::default())
+            .await
+            .unwrap();
         assert_eq!(tested_result, decoded());
     }
+
+    #[tokio::test]
+    async fn decode_reason_codes() {
+        let mut test_data = Cursor::new(vec![5, 57, 0, 0x00, 0x11]);
+        let tested_result = UnSubAck::read(&mut test_data, 5, &Default::default())
+            .await
+            .unwrap();
+        assert_eq!(
+            tested_result.reason_codes,
+            vec![ReasonCode::Success, ReasonCode::NoSubscriptionExisted]
+        );
+    }
+
+    #[tokio::test]
+    async fn decode_invalid_reason_code() {
+        // NoMatchingSubscribers cannot be used in an UNSUBACK packet
+        let mut test_data = Cursor::new(vec![5, 57, 0, 0x00, 0x10]);
+        assert!(matches!(
+            UnSubAck::read(&mut test_data, 5, &Default::default()).await,
+            Err(Error::Reason(ProtocolError))
+        ));
+    }
 }
@@ -1,4 +1,8 @@
-use crate::{codec, PropertiesDecoder, Property, ReasonCode::ProtocolError, Result as SageResult};
+use crate::{
+    codec, DecodeOptions, PropertiesDecoder, Property,
+    ReasonCode::{ProtocolError, TopicFilterInvalid},
+    Result as SageResult,
+};
 use std::marker::Unpin;
 use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 
@@ -26,8 +30,64 @@ impl Default for UnSubscribe {
     }
 }
 
+fn validate_filter(filter: &str) -> SageResult<()> {
+    let mut levels = filter.split('/').peekable();
+    let mut valid = !filter.is_empty();
+    while let Some(level) = levels.next() {
+        valid &= match level {
+            "#" => levels.peek().is_none(),
+            "+" => true,
+            _ => !level.contains(['#', '+']),
+        };
+    }
+    if valid {
+        Ok(())
+    } else {
+        Err(TopicFilterInvalid.into())
+    }
+}
+
 impl UnSubscribe {
+    /// Creates an `UnSubscribe` packet without any topic filter. At least one
+    /// filter must be added before encoding it.
+    pub fn new(packet_identifier: u16) -> Self {
+        UnSubscribe {
+            packet_identifier,
+            ..Default::default()
+        }
+    }
+
+    /// Adds a topic filter to unsubscribe from. Fails with
+    /// `TopicFilterInvalid` if the filter is empty or misuses wildcards.
+    pub fn add_filter(&mut self, filter: &str) -> SageResult<&mut Self> {
+        validate_filter(filter)?;
+        self.subscriptions.push(filter.into());
+        Ok(self)
+    }
+
+    /// Builds an `UnSubscribe` packet out of any iterator of topic filters.
+    /// Fails with `TopicFilterInvalid` if any filter is invalid, and with
+    /// `ProtocolError` if there is no filter.
+    pub fn from_filters<'a, I>(packet_identifier: u16, filters: I) -> SageResult<Self>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let mut unsubscribe = UnSubscribe::new(packet_identifier);
+        for filter in filters {
+            unsubscribe.add_filter(filter)?;
+        }
+        if unsubscribe.subscriptions.is_empty() {
+            Err(ProtocolError.into())
+        } else {
+            Ok(unsubscribe)
+        }
+    }
+
     pub(crate) async fn write<W: AsyncWrite + Unpin>(self, mut  writer: W) -> SageResult<usize> {
+        if self.subscriptions.is_empty() {
+            return Err(ProtocolError.into());
+        }
+
         let mut n_bytes = codec::write_two_byte_integer(self.packet_identifier, &mut writer).await?;
 
         let mut properties = Vec::new();
@@ -47,6 +107,7 @@ impl UnSubscribe {
     pub(crate) async fn read<R: AsyncRead + Unpin>(
         reader: R,
         remaining_size: usize,
+        options: &DecodeOptions,
     ) -> SageResult<Self> {
         let mut reader = reader.take(remaining_size as u64);
 
@@ -54,7 +115,7 @@ impl UnSubscribe {
 
         let mut user_properties = Vec::new();
 
-        let mut properties = PropertiesDecoder::take(&mut reader).await?;
+        let mut properties = PropertiesDecoder::take(&mut reader, options).await?;
         while properties.has_properties() {
             match properties.read().await? {
                 Property::UserProperty(k, v) => user_properties.push((k, v)),
@@ -83,6 +144,7 @@ impl UnSubscribe {
 #[cfg(test)]
 mod unit {
     use super::*;
+    use crate::Error;
     use std::io::Cursor;
 
     fn encoded() -> Vec<u8> {
@@ -118,7 +180,49 @@ mod unit {
     #[tokio::test]
     async fn decode() {
         let mut test_data = Cursor::new(encoded());
-        let tested_result = UnSubscribe::read(&mut test_data, 52).await.unwrap();
+        let tested_result = UnSubscribe::read(&mut test_data, 52, &Default::default())
+            .await
+            .unwrap();
         assert_eq!(tested_result, decoded());
     }
+
+    #[tokio::test]
+    async fn from_filters() {
+        let unsubscribe = UnSubscribe::from_filters(1337, vec!["harder", "better/+/#"]).unwrap();
+        let mut encoded = Vec::new();
+        let n_bytes = unsubscribe.clone().write(&mut encoded).await.unwrap();
+        let mut test_data = Cursor::new(encoded);
+        let tested_result = UnSubscribe::read(&mut test_data, n_bytes, &Default::default())
+            .await
+            .unwrap();
+        assert_eq!(tested_result, unsubscribe);
+        assert_eq!(
+            tested_result.subscriptions,
+            vec![String::from("harder"), String::from("better/+/#")]
+        );
+    }
+
+    #[test]
+    fn add_invalid_filter() {
+        let mut unsubscribe = UnSubscribe::new(1337);
+        for filter in &["", "harder/#/better", "harder+", "faster#"] {
+            assert!(matches!(
+                unsubscribe.add_filter(filter),
+                Err(Error::Reason(TopicFilterInvalid))
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn encode_empty() {
+        let mut tested_result = Vec::new();
+        assert!(matches!(
+            UnSubscribe::new(1337).write(&mut tested_result).await,
+            Err(Error::Reason(ProtocolError))
+        ));
+        assert!(matches!(
+            UnSubscribe::from_filters(1337, std::iter::empty()),
+            Err(Error::Reason(ProtocolError))
+        ));
+    }
 }
@@ -0,0 +1,22 @@
+/// Options tuning how packets are decoded. The default options apply no
+/// restriction beyond the MQTT specification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DecodeOptions {
+    /// Caps the total number of bytes of all the strings read from a single
+    /// property block. Once exceeded, decoding fails with `ProtocolError`.
+    /// This prevents a peer from sending many small strings that fit in the
+    /// remaining length but are abusive as a whole. Unlimited if `None`.
+    pub string_limit: Option<usize>,
+
+    /// Skips the length and character checks on the client identifier of
+    /// `Connect` packets. This is meant for pass-through tools, such as
+    /// proxies, which must not reject clients the server would accept.
+    pub lenient_client_id: bool,
+
+    /// Reads user property values which are not valid UTF-8 as
+    /// `Property::UserPropertyRaw` instead of failing. This is meant for
+    /// pass-through tools reading property blocks with `PropertiesDecoder`.
+    /// Packets store user properties as strings, so decoding a packet still
+    /// fails with `ProtocolError` on such a value.
+    pub lenient_user_properties: bool,
+}
@@ -40,3 +40,88 @@ pub const DEFAULT_SUBSCRIPTION_IDENTIFIER_AVAILABLE: bool = true;
 
 /// Default keep alive
 pub const DEFAULT_KEEP_ALIVE: u16 = 600;
+
+/// All the protocol default values bundled together, as an alternative to the
+/// individual constants of this module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProtocolDefaults {
+    /// Default maximum qos
+    pub maximum_qos: QoS,
+
+    /// Default payload format indicator
+    pub payload_format_indicator: bool,
+
+    /// Default receive maximum
+    pub receive_maximum: u16,
+
+    /// Default request problem information
+    pub request_problem_information: bool,
+
+    /// Default request response information
+    pub request_response_information: bool,
+
+    /// Default retain available
+    pub retain_available: bool,
+
+    /// Default session expiry interval
+    pub session_expiry_interval: Option<u32>,
+
+    /// Default shared subscription available
+    pub shared_subscription_available: bool,
+
+    /// Default topic alias maximum
+    pub topic_alias_maximum: u16,
+
+    /// Default wilcard subscription available
+    pub wildcard_subscription_available: bool,
+
+    /// Default will delay interval
+    pub will_delay_interval: u32,
+
+    /// Default subscription identifier available
+    pub subscription_identifier_available: bool,
+
+    /// Default keep alive
+    pub keep_alive: u16,
+}
+
+impl Default for ProtocolDefaults {
+    fn default() -> Self {
+        ProtocolDefaults {
+            maximum_qos: DEFAULT_MAXIMUM_QOS,
+            payload_format_indicator: DEFAULT_PAYLOAD_FORMAT_INDICATOR,
+            receive_maximum: DEFAULT_RECEIVE_MAXIMUM,
+            request_problem_information: DEFAULT_REQUEST_PROBLEM_INFORMATION,
+            request_response_information: DEFAULT_REQUEST_RESPONSE_INFORMATION,
+            retain_available: DEFAULT_RETAIN_AVAILABLE,
+            session_expiry_interval: DEFAULT_SESSION_EXPIRY_INTERVAL,
+            shared_subscription_available: DEFAULT_SHARED_SUBSCRIPTION_AVAILABLE,
+            topic_alias_maximum: DEFAULT_TOPIC_ALIAS_MAXIMUM,
+            wildcard_subscription_available: DEFAULT_WILCARD_SUBSCRIPTION_AVAILABLE,
+            will_delay_interval: DEFAULT_WILL_DELAY_INTERVAL,
+            subscription_identifier_available: DEFAULT_SUBSCRIPTION_IDENTIFIER_AVAILABLE,
+            keep_alive: DEFAULT_KEEP_ALIVE,
+        }
+    }
+}
+
+#[cfg(test)]
+mod unit {
+
+    use super::*;
+
+    #[test]
+    fn protocol_defaults() {
+        let defaults = ProtocolDefaults::default();
+        assert_eq!(defaults.maximum_qos, DEFAULT_MAXIMUM_QOS);
+        assert_eq!(defaults.receive_maximum, DEFAULT_RECEIVE_MAXIMUM);
+        assert_eq!(defaults.topic_alias_maximum, DEFAULT_TOPIC_ALIAS_MAXIMUM);
+        assert_eq!(defaults.retain_available, DEFAULT_RETAIN_AVAILABLE);
+        assert_eq!(
+            defaults.session_expiry_interval,
+            DEFAULT_SESSION_EXPIRY_INTERVAL
+        );
+        assert_eq!(defaults.will_delay_interval, DEFAULT_WILL_DELAY_INTERVAL);
+        assert_eq!(defaults.keep_alive, DEFAULT_KEEP_ALIVE);
+    }
+}
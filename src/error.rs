@@ -17,13 +17,35 @@ pub enum Error {
 
     /// Error described using a MQTT Reason code
     Reason(ReasonCode),
+
+    /// Error described using a MQTT Reason code along with a message giving
+    /// more context about the failure.
+    Context(ReasonCode, String),
+
+    /// Error which occured while decoding, along with the offset in bytes
+    /// from the start of the packet at which it occured. Only returned by
+    /// `Packet::decode_with_offset`.
+    AtOffset(Box<Error>, u64),
+}
+
+impl Error {
+    /// Strips the decoding offset the error may be wrapped into, returning the
+    /// underlying error.
+    pub fn into_root(self) -> Error {
+        match self {
+            Error::AtOffset(e, _) => e.into_root(),
+            e => e,
+        }
+    }
 }
 
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
         match self {
             Error::Reason(rc) => write!(f, "{:?}", rc),
+            Error::Context(rc, message) => write!(f, "{:?}: {}", rc, message),
             Error::Io(ref e) => e.fmt(f),
+            Error::AtOffset(e, offset) => write!(f, "{} (at byte {})", e, offset),
         }
     }
 }
@@ -32,6 +54,7 @@ impl StdError for Error {
     fn source(&self) -> Option<&(dyn StdError + 'static)> {
         match *self {
             Error::Io(ref e) => Some(e),
+            Error::AtOffset(ref e, _) => Some(e.as_ref()),
             _ => None,
         }
     }
@@ -0,0 +1,50 @@
+use crate::Packet;
+use tokio::runtime::Builder;
+
+/// Decodes `data` as a control packet and, on success, encodes the packet
+/// back. Decoding and encoding errors are ignored: the only failure this
+/// function reports is a panic, which makes it suitable as a libfuzzer target.
+pub fn fuzz_decode(data: &[u8]) {
+    let runtime = Builder::new_current_thread()
+        .build()
+        .expect("cannot build the fuzzing runtime");
+    runtime.block_on(async {
+        if let Ok(packet) = Packet::decode_from_slice(data).await {
+            let _ = packet.encode_to_vec().await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod unit {
+
+    use super::*;
+
+    #[test]
+    fn random_inputs() {
+        // Simple linear congruential generator, good enough to produce
+        // arbitrary bytes without an extra dependency.
+        let mut seed: u32 = 0x5A6E_4D51;
+        for len in 0..512 {
+            let data: Vec<u8> = (0..len % 64)
+                .map(|_| {
+                    seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                    (seed >> 16) as u8
+                })
+                .collect();
+            fuzz_decode(&data);
+        }
+    }
+
+    #[test]
+    fn oversized_remaining_length() {
+        fuzz_decode(&[0x30, 0xFF, 0xFF, 0xFF, 0xFF, 0x7F]);
+    }
+
+    #[test]
+    fn valid_packets() {
+        fuzz_decode(&[0xC0, 0x00]);
+        fuzz_decode(&[0x40, 0x02, 0x00, 0x01]);
+        fuzz_decode(&[0x30, 0x05, 0x00, 0x01, b'a', b'h', b'i']);
+    }
+}
@@ -7,28 +7,55 @@
 #![allow(clippy::large_enum_variant)]
 
 mod authentication;
+mod binary_data;
 /// encode/decode MQTT fundamental types
 pub mod codec;
 mod control;
+mod decode_options;
 pub mod defaults;
 mod error;
+#[cfg(feature = "fuzzing")]
+mod fuzz;
 mod packet;
+mod packet_handler;
+mod packet_size_limiter;
 mod packet_type;
+#[cfg(feature = "diagnostics")]
+mod position_tracking_reader;
 mod property;
+mod qos2_state;
 mod quality_of_service;
 mod reason_code;
+mod receive_quota;
+mod role;
 mod topic;
+mod topic_alias_map;
+mod user_properties;
 mod will;
 pub use authentication::Authentication;
+pub use binary_data::BinaryData;
 pub use control::{
-    Auth, ClientID, ConnAck, Connect, Disconnect, PingReq, PingResp, PubAck, PubComp, PubRec,
-    PubRel, Publish, RetainHandling, SubAck, Subscribe, SubscriptionOptions, UnSubAck, UnSubscribe,
+    Auth, ClientID, ConnAck, Connect, ConnectionContext, Disconnect, NegotiatedSettings, PingReq,
+    PingResp, PubAck, PubComp, PubRec, PubRel, Publish, RetainHandling, SubAck, Subscribe,
+    SubscriptionOptions, UnSubAck, UnSubscribe,
 };
+pub use decode_options::DecodeOptions;
 pub use error::{Error, Result};
+#[cfg(feature = "fuzzing")]
+pub use fuzz::fuzz_decode;
 pub use packet::Packet;
-use packet_type::PacketType;
-use property::{PropertiesDecoder, Property};
+pub use packet_handler::PacketHandler;
+pub use packet_size_limiter::PacketSizeLimiter;
+pub use packet_type::PacketType;
+#[cfg(feature = "diagnostics")]
+pub use position_tracking_reader::PositionTrackingReader;
+pub use property::{properties_equal_unordered, PropertiesDecoder, Property, PropertyId};
+pub use qos2_state::Qos2State;
 pub use quality_of_service::QoS;
-pub use reason_code::ReasonCode;
+pub use reason_code::{LogLevel, ReasonCode};
+pub use receive_quota::ReceiveQuota;
+pub use role::Role;
 pub use topic::Topic;
+pub use topic_alias_map::TopicAliasMap;
+pub use user_properties::UserProperties;
 pub use will::Will;
@@ -1,10 +1,13 @@
+#[cfg(feature = "diagnostics")]
+use crate::PositionTrackingReader;
 use crate::{
-    codec, Auth, ConnAck, Connect, Disconnect, PacketType, PingReq, PingResp, PubAck, PubComp,
-    PubRec, PubRel, Publish, ReasonCode::ProtocolError, Result as SageResult, SubAck, Subscribe,
-    UnSubAck, UnSubscribe,
+    codec, Auth, ConnAck, Connect, DecodeOptions, Disconnect, Error, PacketType, PingReq, PingResp,
+    PubAck, PubComp, PubRec, PubRel, Publish,
+    ReasonCode::{self, MalformedPacket, ProtocolError},
+    Result as SageResult, Role, SubAck, Subscribe, UnSubAck, UnSubscribe,
 };
-use std::{fmt, marker::Unpin};
-use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
+use std::{fmt, marker::Unpin, mem};
+use tokio::io::{self, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 
 #[derive(Debug)]
 struct FixedHeader {
@@ -180,62 +183,58 @@ impl From<Auth> for Packet {
 }
 
 impl Packet {
-    /// Write the entire `Packet` to `writer`, returning the number of
-    /// bytes written.
-    /// In case of failure, the operation will return any MQTT-related error, or
-    /// `std::io::Error`.
-    pub async fn encode<W: AsyncWrite + Unpin>(self, writer: &mut W) -> SageResult<usize> {
-        let mut variable_and_payload = Vec::new();
+    /// Encodes the rest of the packet into `variable_and_payload`, which is
+    /// cleared first, and returns the encoded fixed header.
+    async fn encode_parts(self, variable_and_payload: &mut Vec<u8>) -> SageResult<Vec<u8>> {
+        variable_and_payload.clear();
+        variable_and_payload.reserve(self.remaining_size_hint());
         let (packet_type, remaining_size) = match self {
             Packet::Connect(packet) => (
                 PacketType::Connect,
-                packet.write(&mut variable_and_payload).await?,
+                packet.write(variable_and_payload).await?,
             ),
             Packet::ConnAck(packet) => (
                 PacketType::ConnAck,
-                packet.write(&mut variable_and_payload).await?,
+                packet.write(variable_and_payload).await?,
             ),
             Packet::PingReq => (PacketType::PingReq, 0),
             Packet::PingResp => (PacketType::PingResp, 0),
             Packet::UnSubAck(packet) => (
                 PacketType::UnSubAck,
-                packet.write(&mut variable_and_payload).await?,
-            ),
-            Packet::Auth(packet) => (
-                PacketType::Auth,
-                packet.write(&mut variable_and_payload).await?,
+                packet.write(variable_and_payload).await?,
             ),
+            Packet::Auth(packet) => (PacketType::Auth, packet.write(variable_and_payload).await?),
             Packet::PubAck(packet) => (
                 PacketType::PubAck,
-                packet.write(&mut variable_and_payload).await?,
+                packet.write(variable_and_payload).await?,
             ),
             Packet::UnSubscribe(packet) => (
                 PacketType::UnSubscribe,
-                packet.write(&mut variable_and_payload).await?,
+                packet.write(variable_and_payload).await?,
             ),
             Packet::PubRec(packet) => (
                 PacketType::PubRec,
-                packet.write(&mut variable_and_payload).await?,
+                packet.write(variable_and_payload).await?,
             ),
             Packet::Disconnect(packet) => (
                 PacketType::Disconnect,
-                packet.write(&mut variable_and_payload).await?,
+                packet.write(variable_and_payload).await?,
             ),
             Packet::PubRel(packet) => (
                 PacketType::PubRel,
-                packet.write(&mut variable_and_payload).await?,
+                packet.write(variable_and_payload).await?,
             ),
             Packet::SubAck(packet) => (
                 PacketType::SubAck,
-                packet.write(&mut variable_and_payload).await?,
+                packet.write(variable_and_payload).await?,
             ),
             Packet::PubComp(packet) => (
                 PacketType::PubComp,
-                packet.write(&mut variable_and_payload).await?,
+                packet.write(variable_and_payload).await?,
             ),
             Packet::Subscribe(packet) => (
                 PacketType::Subscribe,
-                packet.write(&mut variable_and_payload).await?,
+                packet.write(variable_and_payload).await?,
             ),
             Packet::Publish(packet) => (
                 PacketType::Publish {
@@ -243,63 +242,281 @@ impl Packet {
                     qos: packet.qos,
                     retain: packet.retain,
                 },
-                packet.write(&mut variable_and_payload).await?,
+                packet.write(variable_and_payload).await?,
             ),
         };
 
         let mut fixed_header_buffer = Vec::new();
 
-        let fixed_size = FixedHeader {
+        FixedHeader {
             packet_type,
             remaining_size,
         }
         .encode(&mut fixed_header_buffer)
         .await?;
 
-        writer.write_all(&fixed_header_buffer).await?;
-        writer.write_all(&variable_and_payload).await?;
-        Ok(fixed_size + remaining_size)
+        Ok(fixed_header_buffer)
+    }
+
+    /// The size of the packet once encoded, fixed header excluded, for the
+    /// packets which can compute it without being encoded. This is `0` for
+    /// other packets, or if the packet cannot be encoded.
+    fn remaining_size_hint(&self) -> usize {
+        match self {
+            Packet::Publish(packet) => packet.encoded_len().unwrap_or_default(),
+            Packet::Subscribe(packet) => packet.encoded_len().unwrap_or_default(),
+            _ => 0,
+        }
+    }
+
+    /// Write the entire `Packet` to `writer`, returning the number of
+    /// bytes written.
+    /// Encoding is deterministic: properties are always written in the order
+    /// of the fields of the packet structure they come from, user properties
+    /// and subscription identifiers keeping their order. Decoding an encoded
+    /// packet and encoding it again gives the same bytes.
+    /// In case of failure, the operation will return any MQTT-related error, or
+    /// `std::io::Error`.
+    pub async fn encode<W: AsyncWrite + Unpin>(self, writer: &mut W) -> SageResult<usize> {
+        self.encode_with_scratch(writer, &mut Vec::new()).await
+    }
+
+    /// Write the entire `Packet` to `writer` like `encode`, using `scratch` to
+    /// hold the packet body before it is written. The buffer is cleared
+    /// first and keeps its capacity afterwards, so that reusing it for many
+    /// packets avoids allocating for each of them.
+    /// In case of failure, the operation will return any MQTT-related error, or
+    /// `std::io::Error`.
+    pub async fn encode_with_scratch<W: AsyncWrite + Unpin>(
+        self,
+        writer: &mut W,
+        scratch: &mut Vec<u8>,
+    ) -> SageResult<usize> {
+        let fixed_header = self.encode_parts(scratch).await?;
+        writer.write_all(&fixed_header).await?;
+        writer.write_all(scratch).await?;
+        Ok(fixed_header.len() + scratch.len())
+    }
+
+    /// Returns the number of bytes the entire `Packet` occupies once encoded,
+    /// fixed header included.
+    /// The size of `Publish` and `Subscribe` packets is computed from their
+    /// fields, without copying nor encoding them.
+    /// In case of failure, the operation will return any MQTT-related error
+    /// that would be returned by `encode`.
+    pub async fn encoded_size(&self) -> SageResult<usize> {
+        let remaining_size = match self {
+            Packet::Publish(packet) => packet.encoded_len()?,
+            Packet::Subscribe(packet) => packet.encoded_len()?,
+            _ => return self.clone().encode(&mut io::sink()).await,
+        };
+        let remaining_size_len =
+            codec::VariableByteInteger::try_new(remaining_size as u32)?.encoded_len();
+        Ok(1 + remaining_size_len + remaining_size)
+    }
+
+    /// Write the entire `Packet` into a new `Vec<u8>`.
+    /// The vector is allocated once with the exact encoded size of the packet,
+    /// so no reallocation happens while filling it.
+    /// In case of failure, the operation will return any MQTT-related error, or
+    /// `std::io::Error`.
+    pub async fn encode_to_vec(self) -> SageResult<Vec<u8>> {
+        let mut variable_and_payload = Vec::new();
+        let fixed_header = self.encode_parts(&mut variable_and_payload).await?;
+        let mut encoded = Vec::with_capacity(fixed_header.len() + variable_and_payload.len());
+        encoded.extend_from_slice(&fixed_header);
+        encoded.extend_from_slice(&variable_and_payload);
+        Ok(encoded)
+    }
+
+    /// Encodes a copy of the `Packet` and formats the bytes as lowercase,
+    /// space-separated hexadecimal values, such as `"c0 00"` for a
+    /// `PingReq`. This is convenient to share the exact content of a packet
+    /// in logs and bug reports.
+    /// In case of failure, the operation will return any MQTT-related error
+    /// that would be returned by `encode`.
+    pub async fn hex_dump(&self) -> SageResult<String> {
+        let encoded = self.clone().encode_to_vec().await?;
+        Ok(encoded
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect::<Vec<_>>()
+            .join(" "))
     }
 
     /// Read a control packet from `reader`, returning a new `Packet`.
     /// In case of failure, the operation will return any MQTT-related error, or
     /// `std::io::Error`.
-    pub async fn decode<R: AsyncRead + Unpin>(mut reader: R) -> SageResult<Self> {
+    pub async fn decode<R: AsyncRead + Unpin>(reader: R) -> SageResult<Self> {
+        Packet::decode_with(reader, &DecodeOptions::default()).await
+    }
+
+    /// Read a control packet from `reader` as `decode` does, applying the
+    /// restrictions given by `options`.
+    pub async fn decode_with<R: AsyncRead + Unpin>(
+        reader: R,
+        options: &DecodeOptions,
+    ) -> SageResult<Self> {
+        Packet::decode_packet(reader, options).await
+    }
+
+    /// Read a control packet from `reader` as `decode` does. In case of
+    /// failure, the error is wrapped into `Error::AtOffset` giving the number
+    /// of bytes read when decoding failed.
+    #[cfg(feature = "diagnostics")]
+    pub async fn decode_with_offset<R: AsyncRead + Unpin>(reader: R) -> SageResult<Self> {
+        let mut reader = PositionTrackingReader::new(reader);
+        Packet::decode_packet(&mut reader, &DecodeOptions::default())
+            .await
+            .map_err(|e| Error::AtOffset(Box::new(e), reader.position()))
+    }
+
+    /// Read a control packet from `reader` as `decode` does, failing with
+    /// `ProtocolError` as soon as the fixed header is read if the packet is
+    /// not of type `expected`. The flags of `Publish` packets are not
+    /// compared.
+    pub async fn decode_expecting<R: AsyncRead + Unpin>(
+        mut reader: R,
+        expected: PacketType,
+    ) -> SageResult<Self> {
+        let fixed_header = FixedHeader::decode(&mut reader).await?;
+        if mem::discriminant(&fixed_header.packet_type) != mem::discriminant(&expected) {
+            return Err(ProtocolError.into());
+        }
+        Packet::read_body(reader, fixed_header, &DecodeOptions::default()).await
+    }
+
+    /// Read a control packet from the beginning of `data`, returning a new
+    /// `Packet`. Any byte following the packet is ignored.
+    /// In case of failure, the operation will return any MQTT-related error, or
+    /// `std::io::Error`.
+    pub async fn decode_from_slice(data: &[u8]) -> SageResult<Self> {
+        Packet::decode(data).await
+    }
+
+    /// Read the fixed header of a control packet from `reader` and the
+    /// `remaining_size` bytes following it, without decoding them. This is
+    /// meant for proxies forwarding packets untouched with `write_raw`.
+    /// In case of failure, the operation will return any MQTT-related error, or
+    /// `std::io::Error`.
+    pub async fn read_raw<R: AsyncRead + Unpin>(
+        mut reader: R,
+    ) -> SageResult<(PacketType, Vec<u8>)> {
         let fixed_header = FixedHeader::decode(&mut reader).await?;
+        // The buffer only grows as bytes arrive, so that a forged remaining
+        // size cannot make it allocate ahead of the data
+        let mut variable_and_payload = Vec::new();
+        reader
+            .take(fixed_header.remaining_size as u64)
+            .read_to_end(&mut variable_and_payload)
+            .await?;
+        if variable_and_payload.len() != fixed_header.remaining_size {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+        Ok((fixed_header.packet_type, variable_and_payload))
+    }
+
+    /// Write a control packet of type `packet_type` whose variable header and
+    /// payload are the already encoded `variable_and_payload`, returning the
+    /// number of bytes written.
+    /// In case of failure, the operation will return any MQTT-related error, or
+    /// `std::io::Error`.
+    pub async fn write_raw<W: AsyncWrite + Unpin>(
+        mut writer: W,
+        packet_type: PacketType,
+        variable_and_payload: &[u8],
+    ) -> SageResult<usize> {
+        let n_bytes = FixedHeader {
+            packet_type,
+            remaining_size: variable_and_payload.len(),
+        }
+        .encode(&mut writer)
+        .await?;
+        writer.write_all(variable_and_payload).await?;
+        Ok(n_bytes + variable_and_payload.len())
+    }
+
+    /// Decodes a control packet of type `packet_type` out of
+    /// `variable_and_payload`, which holds exactly the variable header and
+    /// payload of the packet, without the fixed header. This is the
+    /// counterpart of `read_raw`.
+    /// In case of failure, the operation will return any MQTT-related error, or
+    /// `std::io::Error`.
+    pub async fn decode_body(
+        packet_type: PacketType,
+        variable_and_payload: &[u8],
+    ) -> SageResult<Self> {
+        let fixed_header = FixedHeader {
+            packet_type,
+            remaining_size: variable_and_payload.len(),
+        };
+        Packet::read_body(
+            variable_and_payload,
+            fixed_header,
+            &DecodeOptions::default(),
+        )
+        .await
+    }
+
+    async fn decode_packet<R: AsyncRead + Unpin>(
+        mut reader: R,
+        options: &DecodeOptions,
+    ) -> SageResult<Self> {
+        let fixed_header = FixedHeader::decode(&mut reader).await?;
+        Packet::read_body(reader, fixed_header, options).await
+    }
+
+    async fn read_body<R: AsyncRead + Unpin>(
+        reader: R,
+        fixed_header: FixedHeader,
+        options: &DecodeOptions,
+    ) -> SageResult<Self> {
+        let mut reader = reader.take(fixed_header.remaining_size as u64);
 
         let packet = match fixed_header.packet_type {
-            PacketType::Connect => Packet::Connect(Connect::read(reader).await?),
-            PacketType::ConnAck => Packet::ConnAck(ConnAck::read(reader).await?),
-            PacketType::PubAck => {
-                Packet::PubAck(PubAck::read(reader, fixed_header.remaining_size == 2).await?)
-            }
-            PacketType::PubRec => {
-                Packet::PubRec(PubRec::read(reader, fixed_header.remaining_size == 2).await?)
+            PacketType::Connect => Packet::Connect(
+                Connect::read(&mut reader, fixed_header.remaining_size, options).await?,
+            ),
+            PacketType::ConnAck => Packet::ConnAck(
+                ConnAck::read(&mut reader, fixed_header.remaining_size, options).await?,
+            ),
+            PacketType::PubAck => Packet::PubAck(
+                PubAck::read(&mut reader, fixed_header.remaining_size == 2, options).await?,
+            ),
+            PacketType::PubRec => Packet::PubRec(
+                PubRec::read(&mut reader, fixed_header.remaining_size == 2, options).await?,
+            ),
+            PacketType::PingReq | PacketType::PingResp if fixed_header.remaining_size != 0 => {
+                return Err(MalformedPacket.into())
             }
             PacketType::PingReq => Packet::PingReq,
             PacketType::PingResp => Packet::PingResp,
-            PacketType::SubAck => {
-                Packet::SubAck(SubAck::read(reader, fixed_header.remaining_size).await?)
-            }
-            PacketType::UnSubscribe => {
-                Packet::UnSubscribe(UnSubscribe::read(reader, fixed_header.remaining_size).await?)
-            }
-            PacketType::Auth => Packet::Auth(Auth::read(reader).await?),
-            PacketType::PubRel => {
-                Packet::PubRel(PubRel::read(reader, fixed_header.remaining_size == 2).await?)
+            PacketType::SubAck => Packet::SubAck(
+                SubAck::read(&mut reader, fixed_header.remaining_size, options).await?,
+            ),
+            PacketType::UnSubscribe => Packet::UnSubscribe(
+                UnSubscribe::read(&mut reader, fixed_header.remaining_size, options).await?,
+            ),
+            PacketType::Auth => {
+                Packet::Auth(Auth::read(&mut reader, fixed_header.remaining_size, options).await?)
             }
-            PacketType::Disconnect => Packet::Disconnect(Disconnect::read(reader).await?),
-            PacketType::PubComp => {
-                Packet::PubComp(PubComp::read(reader, fixed_header.remaining_size == 2).await?)
+            PacketType::PubRel => Packet::PubRel(
+                PubRel::read(&mut reader, fixed_header.remaining_size == 2, options).await?,
+            ),
+            PacketType::Disconnect => {
+                Packet::Disconnect(Disconnect::read(&mut reader, options).await?)
             }
+            PacketType::PubComp => Packet::PubComp(
+                PubComp::read(&mut reader, fixed_header.remaining_size == 2, options).await?,
+            ),
 
-            PacketType::Subscribe => {
-                Packet::Subscribe(Subscribe::read(reader, fixed_header.remaining_size).await?)
-            }
+            PacketType::Subscribe => Packet::Subscribe(
+                Subscribe::read(&mut reader, fixed_header.remaining_size, options).await?,
+            ),
 
-            PacketType::UnSubAck => {
-                Packet::UnSubAck(UnSubAck::read(reader, fixed_header.remaining_size).await?)
-            }
+            PacketType::UnSubAck => Packet::UnSubAck(
+                UnSubAck::read(&mut reader, fixed_header.remaining_size, options).await?,
+            ),
 
             PacketType::Publish {
                 duplicate,
@@ -307,17 +524,377 @@ impl Packet {
                 retain,
             } => Packet::Publish(
                 Publish::read(
-                    reader,
+                    &mut reader,
                     duplicate,
                     qos,
                     retain,
                     fixed_header.remaining_size as u64,
+                    options,
                 )
                 .await?,
             ),
             _ => return Err(ProtocolError.into()),
         };
 
+        // Skip whatever was declared in the fixed header but not read, so the
+        // stream is positioned on the next packet.
+        io::copy(&mut reader, &mut io::sink()).await?;
+
         Ok(packet)
     }
+
+    /// Builds a `Disconnect` packet with the given reason code and no
+    /// properties, which is the usual way of shutting a connection down.
+    /// Fails with `ProtocolError` if the reason code cannot be used in a
+    /// `Disconnect` packet.
+    pub fn disconnect(reason_code: ReasonCode) -> SageResult<Self> {
+        if Disconnect::is_valid_reason_code(reason_code) {
+            Ok(Packet::Disconnect(Disconnect {
+                reason_code,
+                ..Default::default()
+            }))
+        } else {
+            Err(Error::Context(
+                ProtocolError,
+                format!("{:?} is not a valid DISCONNECT reason code", reason_code),
+            ))
+        }
+    }
+
+    /// Whether the packet may be sent by an endpoint playing `role` before
+    /// the `ConnAck` packet is received. A client may only send `Connect`
+    /// and `Auth` packets, and a server may only send `ConnAck` and `Auth`
+    /// packets.
+    pub fn allowed_before_connack(&self, role: Role) -> bool {
+        matches!(
+            (role, self),
+            (_, Packet::Auth(_))
+                | (Role::Client, Packet::Connect(_))
+                | (Role::Server, Packet::ConnAck(_))
+        )
+    }
+
+    /// The user properties of the packet, whatever its type. `PingReq` and
+    /// `PingResp` packets have no user properties and return an empty slice.
+    /// The user properties of the will message of a `Connect` packet are not
+    /// included.
+    pub fn user_properties(&self) -> &[(String, String)] {
+        match self {
+            Packet::Connect(packet) => &packet.user_properties,
+            Packet::ConnAck(packet) => &packet.user_properties,
+            Packet::Publish(packet) => &packet.user_properties,
+            Packet::PubAck(packet) => &packet.user_properties,
+            Packet::PubRec(packet) => &packet.user_properties,
+            Packet::PubRel(packet) => &packet.user_properties,
+            Packet::PubComp(packet) => &packet.user_properties,
+            Packet::Subscribe(packet) => &packet.user_properties,
+            Packet::SubAck(packet) => &packet.user_properties,
+            Packet::UnSubscribe(packet) => &packet.user_properties,
+            Packet::UnSubAck(packet) => &packet.user_properties,
+            Packet::PingReq | Packet::PingResp => &[],
+            Packet::Disconnect(packet) => &packet.user_properties,
+            Packet::Auth(packet) => &packet.user_properties,
+        }
+    }
+}
+
+#[cfg(test)]
+mod unit {
+
+    use super::*;
+    use crate::{Publish, QoS, SubscriptionOptions, Topic};
+    use std::io::Cursor;
+
+    #[tokio::test]
+    async fn encode_to_vec_exact_capacity() {
+        let packet: Packet = Subscribe {
+            packet_identifier: 1337,
+            subscriptions: (0..100)
+                .map(|i| {
+                    (
+                        Topic::from(format!("building/{}/+/temperature", i)),
+                        SubscriptionOptions::default(),
+                    )
+                })
+                .collect(),
+            ..Default::default()
+        }
+        .into();
+
+        let encoded_size = packet.encoded_size().await.unwrap();
+        let encoded = packet.encode_to_vec().await.unwrap();
+        assert_eq!(encoded.len(), encoded_size);
+        assert_eq!(encoded.capacity(), encoded.len());
+    }
+
+    #[tokio::test]
+    async fn subscribe_encoded_size_invalid() {
+        let packet: Packet = Subscribe {
+            packet_identifier: 1337,
+            subscription_identifier: Some(0),
+            subscriptions: vec![(Topic::from("sensors/#"), SubscriptionOptions::default())],
+            ..Default::default()
+        }
+        .into();
+
+        assert!(matches!(
+            packet.encoded_size().await,
+            Err(Error::Reason(ProtocolError))
+        ));
+        assert!(matches!(
+            packet.encode_to_vec().await,
+            Err(Error::Reason(ProtocolError))
+        ));
+    }
+
+    #[tokio::test]
+    async fn publish_encoded_size() {
+        let packet: Packet = Publish {
+            qos: QoS::AtLeastOnce,
+            packet_identifier: Some(1337),
+            topic_name: Topic::from("sensors/temperature"),
+            message_expiry_interval: Some(3600),
+            user_properties: vec![("unit".into(), "celsius".into())],
+            message: vec![0x2A; 200],
+            ..Default::default()
+        }
+        .into();
+
+        let encoded_size = packet.encoded_size().await.unwrap();
+        let encoded = packet.encode_to_vec().await.unwrap();
+        assert_eq!(encoded.len(), encoded_size);
+    }
+
+    #[tokio::test]
+    async fn hex_dump() {
+        assert_eq!(Packet::PingReq.hex_dump().await.unwrap(), "c0 00");
+        assert_eq!(
+            Packet::from(PubAck {
+                packet_identifier: 1337,
+                ..Default::default()
+            })
+            .hex_dump()
+            .await
+            .unwrap(),
+            "40 04 05 39 00 00"
+        );
+    }
+
+    #[tokio::test]
+    async fn decode_with_string_limit() {
+        // A DISCONNECT carrying an 11 bytes reason string
+        let mut encoded = vec![0xE0, 0x10, 0x00, 0x0E, 0x1F, 0x00, 0x0B];
+        encoded.extend_from_slice(b"hello world");
+
+        assert!(Packet::decode(&encoded[..]).await.is_ok());
+        let options = DecodeOptions {
+            string_limit: Some(8),
+            ..Default::default()
+        };
+        assert!(matches!(
+            Packet::decode_with(&encoded[..], &options).await,
+            Err(Error::Reason(ProtocolError))
+        ));
+    }
+
+    #[tokio::test]
+    async fn decode_expecting() {
+        let encoded = Packet::from(ConnAck::default())
+            .encode_to_vec()
+            .await
+            .unwrap();
+
+        let mut test_stream = Cursor::new(encoded.clone());
+        assert!(matches!(
+            Packet::decode_expecting(
+                &mut test_stream,
+                PacketType::Publish {
+                    duplicate: false,
+                    qos: QoS::AtMostOnce,
+                    retain: false
+                }
+            )
+            .await,
+            Err(Error::Reason(ProtocolError))
+        ));
+
+        let mut test_stream = Cursor::new(encoded);
+        assert!(matches!(
+            Packet::decode_expecting(&mut test_stream, PacketType::ConnAck).await,
+            Ok(Packet::ConnAck(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn decode_pingreq_with_body() {
+        let mut test_stream = Cursor::new([0xC0, 0x02, 0x00, 0x00]);
+        assert!(matches!(
+            Packet::decode(&mut test_stream).await,
+            Err(Error::Reason(MalformedPacket))
+        ));
+    }
+
+    #[tokio::test]
+    async fn decode_skips_undeclared_bytes() {
+        // A PUBACK declaring a 5 bytes body of which only 4 are meaningful,
+        // followed by a PINGREQ.
+        let mut test_stream = Cursor::new([0x40, 0x05, 0x05, 0x39, 0x00, 0x00, 0x00, 0xC0, 0x00]);
+        assert!(matches!(
+            Packet::decode(&mut test_stream).await,
+            Ok(Packet::PubAck(_))
+        ));
+        assert!(matches!(
+            Packet::decode(&mut test_stream).await,
+            Ok(Packet::PingReq)
+        ));
+    }
+
+    #[test]
+    fn user_properties() {
+        let user_properties = vec![
+            ("Mogwaï".to_string(), "Cat".to_string()),
+            ("Willow".to_string(), "Jaden".to_string()),
+        ];
+        let packet: Packet = Publish {
+            user_properties: user_properties.clone(),
+            ..Default::default()
+        }
+        .into();
+        assert_eq!(packet.user_properties(), &user_properties[..]);
+        assert!(Packet::PingReq.user_properties().is_empty());
+    }
+
+    #[test]
+    fn allowed_before_connack() {
+        let publish: Packet = Publish::new("sensors/temperature", "21.5").into();
+        assert!(!publish.allowed_before_connack(Role::Client));
+        assert!(!publish.allowed_before_connack(Role::Server));
+
+        let auth: Packet = Auth::default().into();
+        assert!(auth.allowed_before_connack(Role::Client));
+        assert!(auth.allowed_before_connack(Role::Server));
+
+        let connect: Packet = Connect::default().into();
+        assert!(connect.allowed_before_connack(Role::Client));
+        assert!(!connect.allowed_before_connack(Role::Server));
+
+        let connack: Packet = ConnAck::default().into();
+        assert!(!connack.allowed_before_connack(Role::Client));
+        assert!(connack.allowed_before_connack(Role::Server));
+    }
+
+    #[tokio::test]
+    async fn disconnect() {
+        let encoded = Packet::disconnect(ReasonCode::ServerBusy)
+            .unwrap()
+            .encode_to_vec()
+            .await
+            .unwrap();
+        let mut test_stream = Cursor::new(encoded);
+        assert!(matches!(
+            Packet::decode(&mut test_stream).await,
+            Ok(Packet::Disconnect(Disconnect {
+                reason_code: ReasonCode::ServerBusy,
+                ..
+            }))
+        ));
+    }
+
+    #[test]
+    fn disconnect_invalid_reason_code() {
+        assert!(matches!(
+            Packet::disconnect(ReasonCode::GrantedQoS1),
+            Err(Error::Context(ProtocolError, _))
+        ));
+    }
+
+    #[tokio::test]
+    async fn read_raw_truncated() {
+        // Announces the maximum remaining size without any byte following
+        let test_data = [0x30, 0xFF, 0xFF, 0xFF, 0x7F];
+        assert!(matches!(
+            Packet::read_raw(&test_data[..]).await,
+            Err(Error::Io(e)) if e.kind() == io::ErrorKind::UnexpectedEof
+        ));
+    }
+
+    #[tokio::test]
+    async fn forward_raw_publish() {
+        let publish = Publish::new("sensors/temperature", "21.5");
+        let encoded = Packet::from(publish.clone()).encode_to_vec().await.unwrap();
+
+        let mut test_stream = Cursor::new(encoded.clone());
+        let (packet_type, variable_and_payload) = Packet::read_raw(&mut test_stream).await.unwrap();
+        assert!(matches!(
+            packet_type,
+            PacketType::Publish {
+                duplicate: false,
+                qos: QoS::AtMostOnce,
+                retain: false
+            }
+        ));
+
+        let mut forwarded = Vec::new();
+        let n_bytes = Packet::write_raw(&mut forwarded, packet_type, &variable_and_payload)
+            .await
+            .unwrap();
+        assert_eq!(n_bytes, encoded.len());
+        assert_eq!(forwarded, encoded);
+
+        let mut test_stream = Cursor::new(forwarded);
+        match Packet::decode(&mut test_stream).await {
+            Ok(Packet::Publish(received)) => assert_eq!(received, publish),
+            _ => panic!("Incorrect packet type"),
+        }
+    }
+
+    #[tokio::test]
+    async fn decode_publish_body() {
+        let publish = Publish::new("sensors/temperature", "21.5");
+        let encoded = Packet::from(publish.clone()).encode_to_vec().await.unwrap();
+        let (packet_type, variable_and_payload) = Packet::read_raw(&encoded[..]).await.unwrap();
+        match Packet::decode_body(packet_type, &variable_and_payload).await {
+            Ok(Packet::Publish(received)) => assert_eq!(received, publish),
+            _ => panic!("Incorrect packet type"),
+        }
+    }
+
+    #[tokio::test]
+    async fn encode_with_scratch() {
+        let first: Packet = Publish::new("sensors/temperature", "21.5").into();
+        let second: Packet = PubAck {
+            packet_identifier: 1337,
+            ..Default::default()
+        }
+        .into();
+
+        let mut scratch = Vec::new();
+        let mut encoded = Vec::new();
+        first
+            .clone()
+            .encode_with_scratch(&mut encoded, &mut scratch)
+            .await
+            .unwrap();
+        second
+            .clone()
+            .encode_with_scratch(&mut encoded, &mut scratch)
+            .await
+            .unwrap();
+
+        let mut expected = first.encode_to_vec().await.unwrap();
+        expected.extend(second.encode_to_vec().await.unwrap());
+        assert_eq!(encoded, expected);
+    }
+
+    #[cfg(feature = "diagnostics")]
+    #[tokio::test]
+    async fn decode_truncated_publish_offset() {
+        // The fixed header announces 10 bytes but the packet stops right
+        // after the topic name
+        let mut test_data = Cursor::new(vec![0x30, 10, 0, 4, 115, 97, 103, 101]);
+        assert!(matches!(
+            Packet::decode_with_offset(&mut test_data).await,
+            Err(Error::AtOffset(e, 8)) if matches!(*e, Error::Io(_))
+        ));
+    }
 }
@@ -0,0 +1,164 @@
+use crate::{
+    Auth, ConnAck, Connect, Disconnect, Packet, PubAck, PubComp, PubRec, PubRel, Publish, SubAck,
+    Subscribe, UnSubAck, UnSubscribe,
+};
+use std::future::Future;
+
+/// A `PacketHandler` provides one method per packet type, called by
+/// `Packet::dispatch` with the content of the packet. All methods do nothing
+/// by default so that an implementation only handles the packets it is
+/// interested in.
+///
+/// The methods return `Send` futures, so that a server can spawn a task
+/// dispatching packets to a handler, provided the handler itself is `Send`.
+/// They can be implemented with `async fn`.
+pub trait PacketHandler {
+    /// Called for `Connect` packets.
+    fn on_connect(&mut self, _packet: Connect) -> impl Future<Output = ()> + Send {
+        async {}
+    }
+
+    /// Called for `ConnAck` packets.
+    fn on_connack(&mut self, _packet: ConnAck) -> impl Future<Output = ()> + Send {
+        async {}
+    }
+
+    /// Called for `Publish` packets.
+    fn on_publish(&mut self, _packet: Publish) -> impl Future<Output = ()> + Send {
+        async {}
+    }
+
+    /// Called for `PubAck` packets.
+    fn on_puback(&mut self, _packet: PubAck) -> impl Future<Output = ()> + Send {
+        async {}
+    }
+
+    /// Called for `PubRec` packets.
+    fn on_pubrec(&mut self, _packet: PubRec) -> impl Future<Output = ()> + Send {
+        async {}
+    }
+
+    /// Called for `PubRel` packets.
+    fn on_pubrel(&mut self, _packet: PubRel) -> impl Future<Output = ()> + Send {
+        async {}
+    }
+
+    /// Called for `PubComp` packets.
+    fn on_pubcomp(&mut self, _packet: PubComp) -> impl Future<Output = ()> + Send {
+        async {}
+    }
+
+    /// Called for `Subscribe` packets.
+    fn on_subscribe(&mut self, _packet: Subscribe) -> impl Future<Output = ()> + Send {
+        async {}
+    }
+
+    /// Called for `SubAck` packets.
+    fn on_suback(&mut self, _packet: SubAck) -> impl Future<Output = ()> + Send {
+        async {}
+    }
+
+    /// Called for `UnSubscribe` packets.
+    fn on_unsubscribe(&mut self, _packet: UnSubscribe) -> impl Future<Output = ()> + Send {
+        async {}
+    }
+
+    /// Called for `UnSubAck` packets.
+    fn on_unsuback(&mut self, _packet: UnSubAck) -> impl Future<Output = ()> + Send {
+        async {}
+    }
+
+    /// Called for `PingReq` packets.
+    fn on_pingreq(&mut self) -> impl Future<Output = ()> + Send {
+        async {}
+    }
+
+    /// Called for `PingResp` packets.
+    fn on_pingresp(&mut self) -> impl Future<Output = ()> + Send {
+        async {}
+    }
+
+    /// Called for `Disconnect` packets.
+    fn on_disconnect(&mut self, _packet: Disconnect) -> impl Future<Output = ()> + Send {
+        async {}
+    }
+
+    /// Called for `Auth` packets.
+    fn on_auth(&mut self, _packet: Auth) -> impl Future<Output = ()> + Send {
+        async {}
+    }
+}
+
+impl Packet {
+    /// Calls the method of `handler` matching the type of the packet.
+    pub async fn dispatch(self, handler: &mut impl PacketHandler) {
+        match self {
+            Packet::Connect(packet) => handler.on_connect(packet).await,
+            Packet::ConnAck(packet) => handler.on_connack(packet).await,
+            Packet::Publish(packet) => handler.on_publish(packet).await,
+            Packet::PubAck(packet) => handler.on_puback(packet).await,
+            Packet::PubRec(packet) => handler.on_pubrec(packet).await,
+            Packet::PubRel(packet) => handler.on_pubrel(packet).await,
+            Packet::PubComp(packet) => handler.on_pubcomp(packet).await,
+            Packet::Subscribe(packet) => handler.on_subscribe(packet).await,
+            Packet::SubAck(packet) => handler.on_suback(packet).await,
+            Packet::UnSubscribe(packet) => handler.on_unsubscribe(packet).await,
+            Packet::UnSubAck(packet) => handler.on_unsuback(packet).await,
+            Packet::PingReq => handler.on_pingreq().await,
+            Packet::PingResp => handler.on_pingresp().await,
+            Packet::Disconnect(packet) => handler.on_disconnect(packet).await,
+            Packet::Auth(packet) => handler.on_auth(packet).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod unit {
+
+    use super::*;
+
+    #[derive(Default)]
+    struct Recorder {
+        calls: Vec<&'static str>,
+    }
+
+    impl PacketHandler for Recorder {
+        async fn on_publish(&mut self, _packet: Publish) {
+            self.calls.push("on_publish");
+        }
+
+        async fn on_pingreq(&mut self) {
+            self.calls.push("on_pingreq");
+        }
+    }
+
+    #[tokio::test]
+    async fn dispatch() {
+        let mut recorder = Recorder::default();
+        Packet::from(Publish::new("sensors/temperature", "21.5"))
+            .dispatch(&mut recorder)
+            .await;
+        assert_eq!(recorder.calls, vec!["on_publish"]);
+
+        Packet::PingResp.dispatch(&mut recorder).await;
+        assert_eq!(recorder.calls, vec!["on_publish"]);
+    }
+
+    fn spawn_dispatch<H: PacketHandler + Send + 'static>(
+        packet: Packet,
+        mut handler: H,
+    ) -> tokio::task::JoinHandle<H> {
+        tokio::spawn(async move {
+            packet.dispatch(&mut handler).await;
+            handler
+        })
+    }
+
+    #[tokio::test]
+    async fn dispatch_spawned() {
+        let recorder = spawn_dispatch(Packet::PingReq, Recorder::default())
+            .await
+            .unwrap();
+        assert_eq!(recorder.calls, vec!["on_pingreq"]);
+    }
+}
@@ -0,0 +1,73 @@
+use crate::{Packet, ReasonCode::PacketTooLarge, Result as SageResult};
+use std::marker::Unpin;
+use tokio::io::AsyncWrite;
+
+/// A writer wrapper refusing to encode packets bigger than the maximum packet
+/// size advertised by the peer in its `Connect` or `ConnAck` packet.
+#[derive(Debug)]
+pub struct PacketSizeLimiter<W> {
+    inner: W,
+    maximum_packet_size: u32,
+}
+
+impl<W> PacketSizeLimiter<W> {
+    /// Wraps `inner`, limiting the size of each encoded packet, fixed header
+    /// included, to `maximum_packet_size` bytes.
+    pub fn new(inner: W, maximum_packet_size: u32) -> Self {
+        PacketSizeLimiter {
+            inner,
+            maximum_packet_size,
+        }
+    }
+
+    /// The maximum size of a packet, in bytes.
+    pub fn maximum_packet_size(&self) -> u32 {
+        self.maximum_packet_size
+    }
+
+    /// Unwraps the inner writer.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: AsyncWrite + Unpin> PacketSizeLimiter<W> {
+    /// Encodes `packet` to the inner writer as `Packet::encode` does,
+    /// returning the number of bytes written.
+    /// Fails with `PacketTooLarge` without writing anything if the encoded
+    /// packet would exceed the maximum packet size.
+    pub async fn encode(&mut self, packet: Packet) -> SageResult<usize> {
+        if packet.encoded_size().await? > self.maximum_packet_size as usize {
+            Err(PacketTooLarge.into())
+        } else {
+            packet.encode(&mut self.inner).await
+        }
+    }
+}
+
+#[cfg(test)]
+mod unit {
+
+    use super::*;
+    use crate::{Error, Publish};
+
+    #[tokio::test]
+    async fn encode() {
+        let mut limiter = PacketSizeLimiter::new(Vec::new(), 32);
+
+        let n_bytes = limiter
+            .encode(Publish::new("sensors/temperature", "21.5").into())
+            .await
+            .unwrap();
+        assert_eq!(limiter.into_inner().len(), n_bytes);
+
+        let mut limiter = PacketSizeLimiter::new(Vec::new(), 32);
+        assert!(matches!(
+            limiter
+                .encode(Publish::new("sensors/temperature", vec![0x2A; 32]).into())
+                .await,
+            Err(Error::Reason(PacketTooLarge))
+        ));
+        assert!(limiter.into_inner().is_empty());
+    }
+}
@@ -1,4 +1,5 @@
-use crate::QoS;
+use crate::{PropertyId, QoS};
+use std::fmt;
 
 /// The control packet type is present as the first element of the fixed header
 /// in an MQTT paquet. It is encoded in a 8bit flag set where the 4 most
@@ -6,28 +7,89 @@ use crate::QoS;
 /// where values depend on the type.
 #[derive(Debug, Clone, Copy)]
 pub enum PacketType {
+    /// Reserved value, forbidden on the wire.
     Reserved,
+    /// CONNECT packet type.
     Connect,
+    /// CONNACK packet type.
     ConnAck,
+    /// PUBLISH packet type, along with its fixed header flags.
     Publish {
+        /// Whether the packet is a redelivery.
         duplicate: bool,
+        /// The quality of service of the message.
         qos: QoS,
+        /// Whether the message must be retained by the server.
         retain: bool,
     },
+    /// PUBACK packet type.
     PubAck,
+    /// PUBREC packet type.
     PubRec,
+    /// PUBREL packet type.
     PubRel,
+    /// PUBCOMP packet type.
     PubComp,
+    /// SUBSCRIBE packet type.
     Subscribe,
+    /// SUBACK packet type.
     SubAck,
+    /// UNSUBSCRIBE packet type.
     UnSubscribe,
+    /// UNSUBACK packet type.
     UnSubAck,
+    /// PINGREQ packet type.
     PingReq,
+    /// PINGRESP packet type.
     PingResp,
+    /// DISCONNECT packet type.
     Disconnect,
+    /// AUTH packet type.
     Auth,
 }
 
+impl PacketType {
+    /// The name of the packet type as written in the MQTT specification, such
+    /// as `"CONNECT"` or `"PUBLISH"`. Flags are ignored.
+    pub fn name(&self) -> &'static str {
+        match self {
+            PacketType::Reserved => "RESERVED",
+            PacketType::Connect => "CONNECT",
+            PacketType::ConnAck => "CONNACK",
+            PacketType::Publish { .. } => "PUBLISH",
+            PacketType::PubAck => "PUBACK",
+            PacketType::PubRec => "PUBREC",
+            PacketType::PubRel => "PUBREL",
+            PacketType::PubComp => "PUBCOMP",
+            PacketType::Subscribe => "SUBSCRIBE",
+            PacketType::SubAck => "SUBACK",
+            PacketType::UnSubscribe => "UNSUBSCRIBE",
+            PacketType::UnSubAck => "UNSUBACK",
+            PacketType::PingReq => "PINGREQ",
+            PacketType::PingResp => "PINGRESP",
+            PacketType::Disconnect => "DISCONNECT",
+            PacketType::Auth => "AUTH",
+        }
+    }
+
+    /// The identifiers of the properties a packet of this type may contain,
+    /// in increasing order of value. Will properties are considered part of
+    /// `Connect` packets.
+    pub fn allowed_properties(&self) -> Vec<PropertyId> {
+        PropertyId::ALL
+            .iter()
+            .copied()
+            .filter(|id| id.is_allowed_in(*self))
+            .collect()
+    }
+}
+
+impl fmt::Display for PacketType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
 enum PayloadRequirements {
     None,
     Required,
@@ -47,3 +109,40 @@ impl From<PacketType> for PayloadRequirements {
         }
     }
 }
+
+#[cfg(test)]
+mod unit {
+
+    use super::*;
+
+    #[test]
+    fn display() {
+        assert_eq!(PacketType::Connect.to_string(), "CONNECT");
+        assert_eq!(PacketType::UnSubAck.to_string(), "UNSUBACK");
+    }
+
+    #[test]
+    fn display_publish_ignores_flags() {
+        let packet_type = PacketType::Publish {
+            duplicate: true,
+            qos: QoS::ExactlyOnce,
+            retain: true,
+        };
+        assert_eq!(packet_type.to_string(), "PUBLISH");
+        assert_eq!(packet_type.name(), "PUBLISH");
+    }
+
+    #[test]
+    fn allowed_properties() {
+        let connack = PacketType::ConnAck.allowed_properties();
+        assert!(connack.contains(&PropertyId::AssignedClientIdentifier));
+        assert!(connack.contains(&PropertyId::ServerKeepAlive));
+
+        let connect = PacketType::Connect.allowed_properties();
+        assert!(!connect.contains(&PropertyId::AssignedClientIdentifier));
+        assert!(!connect.contains(&PropertyId::ServerKeepAlive));
+        assert!(connect.contains(&PropertyId::WillDelayInterval));
+
+        assert!(PacketType::PingReq.allowed_properties().is_empty());
+    }
+}
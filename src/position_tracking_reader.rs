@@ -0,0 +1,65 @@
+use std::{
+    io::Result as IoResult,
+    marker::Unpin,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tokio::io::{AsyncRead, ReadBuf};
+
+/// A reader wrapper counting the bytes read from the inner reader. This is
+/// used to report the offset at which decoding failed.
+#[derive(Debug)]
+pub struct PositionTrackingReader<R> {
+    inner: R,
+    position: u64,
+}
+
+impl<R> PositionTrackingReader<R> {
+    /// Wraps `inner`, starting at position `0`.
+    pub fn new(inner: R) -> Self {
+        PositionTrackingReader { inner, position: 0 }
+    }
+
+    /// The number of bytes read so far.
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Unwraps the inner reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for PositionTrackingReader<R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<IoResult<()>> {
+        let filled = buf.filled().len();
+        let poll = Pin::new(&mut self.inner).poll_read(cx, buf);
+        self.position += (buf.filled().len() - filled) as u64;
+        poll
+    }
+}
+
+#[cfg(test)]
+mod unit {
+
+    use super::*;
+    use std::io::Cursor;
+    use tokio::io::AsyncReadExt;
+
+    #[tokio::test]
+    async fn position() {
+        let mut reader = PositionTrackingReader::new(Cursor::new(vec![0x2A; 8]));
+        let mut buffer = [0u8; 3];
+        reader.read_exact(&mut buffer).await.unwrap();
+        assert_eq!(reader.position(), 3);
+        reader.read_exact(&mut buffer).await.unwrap();
+        assert_eq!(reader.position(), 6);
+        assert!(reader.read_exact(&mut buffer).await.is_err());
+        assert_eq!(reader.position(), 8);
+    }
+}
@@ -4,10 +4,10 @@ use crate::{
         DEFAULT_MAXIMUM_QOS, DEFAULT_PAYLOAD_FORMAT_INDICATOR, DEFAULT_RECEIVE_MAXIMUM,
         DEFAULT_REQUEST_PROBLEM_INFORMATION, DEFAULT_REQUEST_RESPONSE_INFORMATION,
         DEFAULT_RETAIN_AVAILABLE, DEFAULT_SHARED_SUBSCRIPTION_AVAILABLE,
-        DEFAULT_TOPIC_ALIAS_MAXIMUM, DEFAULT_WILCARD_SUBSCRIPTION_AVAILABLE,
-        DEFAULT_WILL_DELAY_INTERVAL,
+        DEFAULT_SUBSCRIPTION_IDENTIFIER_AVAILABLE, DEFAULT_TOPIC_ALIAS_MAXIMUM,
+        DEFAULT_WILCARD_SUBSCRIPTION_AVAILABLE, DEFAULT_WILL_DELAY_INTERVAL,
     },
-    QoS,
+    DecodeOptions, PacketType, QoS,
     ReasonCode::{MalformedPacket, ProtocolError},
     Result as SageResult, Topic,
 };
@@ -15,37 +15,175 @@ use std::collections::HashSet;
 use std::marker::Unpin;
 use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, Take};
 
-#[derive(PartialEq, Eq, Hash, Copy, Clone)]
-enum PropertyId {
+/// The identifier of a property, as encoded before its value.
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub enum PropertyId {
+    /// Whether the payload is UTF-8 encoded character data.
     PayloadFormatIndicator = 0x01,
+
+    /// The lifetime of a message, in seconds.
     MessageExpiryInterval = 0x02,
+
+    /// The content type of a message, usually a MIME type.
     ContentType = 0x03,
+
+    /// The topic name for a response message.
     ResponseTopic = 0x08,
+
+    /// Data used to match a response with a request.
     CorrelationData = 0x09,
+
+    /// The identifier of a subscription.
     SubscriptionIdentifier = 0x0B,
+
+    /// The lifetime of a session once the connection is closed, in seconds.
     SessionExpiryInterval = 0x11,
+
+    /// The client identifier assigned by the server.
     AssignedClientIdentifier = 0x12,
+
+    /// The keep alive value imposed by the server, in seconds.
     ServerKeepAlive = 0x13,
+
+    /// The method used for enhanced authentication.
     AuthenticationMethod = 0x15,
+
+    /// The data exchanged during enhanced authentication.
     AuthenticationData = 0x16,
+
+    /// Whether the client accepts problem information.
     RequestProblemInformation = 0x17,
+
+    /// The delay before publishing a will message, in seconds.
     WillDelayInterval = 0x18,
+
+    /// Whether the client requests response information.
     RequestResponseInformation = 0x19,
+
+    /// The basis for creating response topics.
     ResponseInformation = 0x1A,
+
+    /// Another server the client can use.
     ServerReference = 0x1C,
+
+    /// A human readable description of a reason code.
     ReasonString = 0x1F,
+
+    /// The number of QoS 1 and QoS 2 messages processed concurrently.
     ReceiveMaximum = 0x21,
+
+    /// The highest accepted topic alias.
     TopicAliasMaximum = 0x22,
+
+    /// An integer replacing a topic name.
     TopicAlias = 0x23,
+
+    /// The highest quality of service supported by the server.
     MaximumQoS = 0x24,
+
+    /// Whether the server supports retained messages.
     RetainAvailable = 0x25,
+
+    /// A general purpose key-value pair.
     UserProperty = 0x26,
+
+    /// The maximum size of a packet the sender accepts.
     MaximumPacketSize = 0x27,
+
+    /// Whether the server supports wildcard subscriptions.
     WildcardSubscriptionAvailable = 0x28,
+
+    /// Whether the server supports subscription identifiers.
     SubscriptionIdentifiersAvailable = 0x29,
+
+    /// Whether the server supports shared subscriptions.
     SharedSubscriptionAvailable = 0x2A,
 }
 
+impl PropertyId {
+    /// Every property identifier, in increasing order of value.
+    pub(crate) const ALL: [PropertyId; 27] = [
+        PropertyId::PayloadFormatIndicator,
+        PropertyId::MessageExpiryInterval,
+        PropertyId::ContentType,
+        PropertyId::ResponseTopic,
+        PropertyId::CorrelationData,
+        PropertyId::SubscriptionIdentifier,
+        PropertyId::SessionExpiryInterval,
+        PropertyId::AssignedClientIdentifier,
+        PropertyId::ServerKeepAlive,
+        PropertyId::AuthenticationMethod,
+        PropertyId::AuthenticationData,
+        PropertyId::RequestProblemInformation,
+        PropertyId::WillDelayInterval,
+        PropertyId::RequestResponseInformation,
+        PropertyId::ResponseInformation,
+        PropertyId::ServerReference,
+        PropertyId::ReasonString,
+        PropertyId::ReceiveMaximum,
+        PropertyId::TopicAliasMaximum,
+        PropertyId::TopicAlias,
+        PropertyId::MaximumQoS,
+        PropertyId::RetainAvailable,
+        PropertyId::UserProperty,
+        PropertyId::MaximumPacketSize,
+        PropertyId::WildcardSubscriptionAvailable,
+        PropertyId::SubscriptionIdentifiersAvailable,
+        PropertyId::SharedSubscriptionAvailable,
+    ];
+
+    /// Checks whether the property may be carried by a packet of the given
+    /// type. Will properties are considered part of the `Connect` packet.
+    pub(crate) fn is_allowed_in(self, packet_type: PacketType) -> bool {
+        use PacketType::*;
+        match self {
+            PropertyId::PayloadFormatIndicator
+            | PropertyId::MessageExpiryInterval
+            | PropertyId::ContentType
+            | PropertyId::ResponseTopic
+            | PropertyId::CorrelationData => matches!(packet_type, Connect | Publish { .. }),
+            PropertyId::SubscriptionIdentifier => {
+                matches!(packet_type, Publish { .. } | Subscribe)
+            }
+            PropertyId::SessionExpiryInterval => {
+                matches!(packet_type, Connect | ConnAck | Disconnect)
+            }
+            PropertyId::AuthenticationMethod | PropertyId::AuthenticationData => {
+                matches!(packet_type, Connect | ConnAck | Auth)
+            }
+            PropertyId::RequestProblemInformation
+            | PropertyId::WillDelayInterval
+            | PropertyId::RequestResponseInformation => matches!(packet_type, Connect),
+            PropertyId::ReceiveMaximum
+            | PropertyId::TopicAliasMaximum
+            | PropertyId::MaximumPacketSize => matches!(packet_type, Connect | ConnAck),
+            PropertyId::AssignedClientIdentifier
+            | PropertyId::ServerKeepAlive
+            | PropertyId::ResponseInformation
+            | PropertyId::MaximumQoS
+            | PropertyId::RetainAvailable
+            | PropertyId::WildcardSubscriptionAvailable
+            | PropertyId::SubscriptionIdentifiersAvailable
+            | PropertyId::SharedSubscriptionAvailable => matches!(packet_type, ConnAck),
+            PropertyId::ServerReference => matches!(packet_type, ConnAck | Disconnect),
+            PropertyId::TopicAlias => matches!(packet_type, Publish { .. }),
+            PropertyId::ReasonString => matches!(
+                packet_type,
+                ConnAck
+                    | PubAck
+                    | PubRec
+                    | PubRel
+                    | PubComp
+                    | SubAck
+                    | UnSubAck
+                    | Disconnect
+                    | Auth
+            ),
+            PropertyId::UserProperty => !matches!(packet_type, Reserved | PingReq | PingResp),
+        }
+    }
+}
+
 async fn write_property_id<W: AsyncWrite + Unpin>(
     id: PropertyId,
     writer: &mut W,
@@ -86,61 +224,118 @@ async fn read_property_id<R: AsyncRead + Unpin>(reader: &mut R) -> SageResult<Pr
     }
 }
 
-#[derive(Debug, PartialEq)]
+/// A property as found in the property block of MQTT packets.
+#[derive(Debug, PartialEq, Clone)]
 #[allow(clippy::enum_variant_names)]
 pub enum Property {
+    /// Whether the payload is UTF-8 encoded character data.
     PayloadFormatIndicator(bool),
+    /// Lifetime of the application message in seconds.
     MessageExpiryInterval(u32),
+    /// Describes the content of the application message, generally a MIME type.
     ContentType(String),
+    /// Topic name used for a response message.
     ResponseTopic(Topic),
+    /// Data used by the sender of a request message to identify the response.
     CorrelationData(Vec<u8>),
+    /// Identifier of a subscription.
     SubscriptionIdentifier(u32),
+    /// Session expiry interval in seconds.
     SessionExpiryInterval(u32),
+    /// Client identifier assigned by the server.
     AssignedClientIdentifier(String),
+    /// Keep alive time assigned by the server, in seconds.
     ServerKeepAlive(u16),
+    /// Name of the method used for extended authentication.
     AuthenticationMethod(String),
+    /// Authentication data, whose content is defined by the authentication method.
     AuthenticationData(Vec<u8>),
+    /// Whether the reason string or user properties are sent in case of failures.
     RequestProblemInformation(bool),
+    /// Will delay interval in seconds.
     WillDelayInterval(u32),
+    /// Whether the client requests the server to return response information.
     RequestResponseInformation(bool),
+    /// Basis for creating a response topic.
     ResponseInformation(String),
+    /// Another server the client can use.
     ServerReference(String),
+    /// Human readable string designed for diagnostics.
     ReasonString(String),
+    /// Maximum number of QoS 1 and QoS 2 publications processed concurrently.
     ReceiveMaximum(u16),
+    /// Highest value accepted as a topic alias.
     TopicAliasMaximum(u16),
+    /// Value used to identify the topic instead of using the topic name.
     TopicAlias(u16),
+    /// Maximum quality of service supported by the server.
     MaximumQoS(QoS),
+    /// Whether the server supports retained messages.
     RetainAvailable(bool),
+    /// General purpose name-value pair.
     UserProperty(String, String),
+    /// General purpose name-value pair whose value is not valid UTF-8. Only
+    /// produced when decoding with `DecodeOptions::lenient_user_properties`,
+    /// and encoded back as the same raw bytes.
+    UserPropertyRaw(String, Vec<u8>),
+    /// Maximum packet size accepted, in bytes.
     MaximumPacketSize(u32),
+    /// Whether the server supports wildcard subscriptions.
     WildcardSubscriptionAvailable(bool),
+    /// Whether the server supports subscription identifiers.
     SubscriptionIdentifiersAvailable(bool),
+    /// Whether the server supports shared subscriptions.
     SharedSubscriptionAvailable(bool),
 }
 
+/// Reads the properties of a property block one at a time, enforcing the
+/// unicity of the properties which may only appear once and the limits of
+/// the `DecodeOptions`.
 pub struct PropertiesDecoder<R: AsyncRead + Unpin> {
     reader: Take<R>,
     marked: HashSet<PropertyId>,
+    string_limit: Option<usize>,
+    string_bytes: usize,
+    explicit_defaults: bool,
+    lenient_user_properties: bool,
 }
 
 impl<'a, R: AsyncRead + Unpin> PropertiesDecoder<R> {
-    pub async fn take(mut stream: R) -> SageResult<Self> {
+    /// Reads the length of the property block from `stream` and prepares to
+    /// read the properties it contains.
+    pub async fn take(mut stream: R, options: &DecodeOptions) -> SageResult<Self> {
         let len = codec::read_variable_byte_integer(&mut stream).await? as u64;
         let reader = stream.take(len);
         Ok(PropertiesDecoder {
             reader,
             marked: HashSet::new(),
+            string_limit: options.string_limit,
+            string_bytes: 0,
+            explicit_defaults: false,
+            lenient_user_properties: options.lenient_user_properties,
         })
     }
 
+    /// Gives back the underlying stream, which may still hold unread
+    /// properties.
     pub fn into_inner(self) -> R {
         self.reader.into_inner()
     }
 
+    /// Whether the property block still holds properties to read.
     pub fn has_properties(&self) -> bool {
         self.reader.limit() > 0
     }
 
+    /// Whether any of the properties read so far was explicitly sent with its
+    /// default value. This is legal, but a sender should omit such
+    /// properties, which conformance tools may want to report.
+    pub fn saw_explicit_defaults(&self) -> bool {
+        self.explicit_defaults
+    }
+
+    /// Reads the next property of the block. Fails with `ProtocolError` if a
+    /// property appears twice while it may only appear once.
     pub async fn read(&mut self) -> SageResult<Property> {
         let reader = &mut self.reader;
         let property_id = read_property_id(reader).await?;
@@ -152,7 +347,17 @@ impl<'a, R: AsyncRead + Unpin> PropertiesDecoder<R> {
         {
             return Err(ProtocolError.into());
         }
-        self.read_property_value(property_id).await
+        let property = self.read_property_value(property_id).await?;
+        self.explicit_defaults |= property.is_default();
+
+        if let Some(limit) = self.string_limit {
+            self.string_bytes += property.string_bytes();
+            if self.string_bytes > limit {
+                return Err(ProtocolError.into());
+            }
+        }
+
+        Ok(property)
     }
 
     async fn read_property_value(&mut self, id: PropertyId) -> SageResult<Property> {
@@ -242,10 +447,20 @@ impl<'a, R: AsyncRead + Unpin> PropertiesDecoder<R> {
             PropertyId::RetainAvailable => {
                 Ok(Property::RetainAvailable(codec::read_bool(reader).await?))
             }
-            PropertyId::UserProperty => Ok(Property::UserProperty(
-                codec::read_utf8_string(reader).await?,
-                codec::read_utf8_string(reader).await?,
-            )),
+            PropertyId::UserProperty => {
+                let k = codec::read_utf8_string(reader).await?;
+                if self.lenient_user_properties {
+                    match String::from_utf8(codec::read_binary_data(reader).await?) {
+                        Ok(v) => Ok(Property::UserProperty(k, v)),
+                        Err(e) => Ok(Property::UserPropertyRaw(k, e.into_bytes())),
+                    }
+                } else {
+                    Ok(Property::UserProperty(
+                        k,
+                        codec::read_utf8_string(reader).await?,
+                    ))
+                }
+            }
             PropertyId::MaximumPacketSize => Ok(Property::MaximumPacketSize(
                 codec::read_four_byte_integer(reader).await?,
             )),
@@ -262,17 +477,195 @@ impl<'a, R: AsyncRead + Unpin> PropertiesDecoder<R> {
     }
 }
 
+impl<R: AsyncRead + Unpin> PropertiesDecoder<&mut Take<R>> {
+    /// Checks the property block, as declared by its length, fits into the
+    /// enclosing bounded reader. Returns `MalformedPacket` otherwise.
+    pub fn check_bounds(&self) -> SageResult<()> {
+        if self.reader.limit() > self.reader.get_ref().limit() {
+            Err(MalformedPacket.into())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Compares two lists of properties regardless of their order. Lists are
+/// compared as multisets, so a property given twice in `a` must also be given
+/// twice in `b`, which matters for user properties.
+pub fn properties_equal_unordered(a: &[Property], b: &[Property]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut matched = vec![false; b.len()];
+    a.iter().all(
+        |property| match (0..b.len()).find(|&i| !matched[i] && b[i] == *property) {
+            Some(i) => {
+                matched[i] = true;
+                true
+            }
+            None => false,
+        },
+    )
+}
+
 impl Property {
-    pub async fn encode<W: AsyncWrite + Unpin>(self, writer: &mut W) -> SageResult<usize> {
+    pub(crate) fn id(&self) -> PropertyId {
+        match self {
+            Property::PayloadFormatIndicator(..) => PropertyId::PayloadFormatIndicator,
+            Property::MessageExpiryInterval(..) => PropertyId::MessageExpiryInterval,
+            Property::ContentType(..) => PropertyId::ContentType,
+            Property::ResponseTopic(..) => PropertyId::ResponseTopic,
+            Property::CorrelationData(..) => PropertyId::CorrelationData,
+            Property::SubscriptionIdentifier(..) => PropertyId::SubscriptionIdentifier,
+            Property::SessionExpiryInterval(..) => PropertyId::SessionExpiryInterval,
+            Property::AssignedClientIdentifier(..) => PropertyId::AssignedClientIdentifier,
+            Property::ServerKeepAlive(..) => PropertyId::ServerKeepAlive,
+            Property::AuthenticationMethod(..) => PropertyId::AuthenticationMethod,
+            Property::AuthenticationData(..) => PropertyId::AuthenticationData,
+            Property::RequestProblemInformation(..) => PropertyId::RequestProblemInformation,
+            Property::WillDelayInterval(..) => PropertyId::WillDelayInterval,
+            Property::RequestResponseInformation(..) => PropertyId::RequestResponseInformation,
+            Property::ResponseInformation(..) => PropertyId::ResponseInformation,
+            Property::ServerReference(..) => PropertyId::ServerReference,
+            Property::ReasonString(..) => PropertyId::ReasonString,
+            Property::ReceiveMaximum(..) => PropertyId::ReceiveMaximum,
+            Property::TopicAliasMaximum(..) => PropertyId::TopicAliasMaximum,
+            Property::TopicAlias(..) => PropertyId::TopicAlias,
+            Property::MaximumQoS(..) => PropertyId::MaximumQoS,
+            Property::RetainAvailable(..) => PropertyId::RetainAvailable,
+            Property::UserProperty(..) | Property::UserPropertyRaw(..) => PropertyId::UserProperty,
+            Property::MaximumPacketSize(..) => PropertyId::MaximumPacketSize,
+            Property::WildcardSubscriptionAvailable(..) => {
+                PropertyId::WildcardSubscriptionAvailable
+            }
+            Property::SubscriptionIdentifiersAvailable(..) => {
+                PropertyId::SubscriptionIdentifiersAvailable
+            }
+            Property::SharedSubscriptionAvailable(..) => PropertyId::SharedSubscriptionAvailable,
+        }
+    }
+
+    fn string_bytes(&self) -> usize {
+        match self {
+            Property::ContentType(v)
+            | Property::AssignedClientIdentifier(v)
+            | Property::AuthenticationMethod(v)
+            | Property::ResponseInformation(v)
+            | Property::ServerReference(v)
+            | Property::ReasonString(v) => v.len(),
+            Property::ResponseTopic(v) => v.to_string().len(),
+            Property::UserProperty(k, v) => k.len() + v.len(),
+            Property::UserPropertyRaw(k, v) => k.len() + v.len(),
+            _ => 0,
+        }
+    }
+
+    /// Checks whether the property may be carried by a packet of the given
+    /// type, as specified by MQTT 5. Will properties are considered part of
+    /// the `Connect` packet.
+    pub fn is_allowed_in(&self, packet_type: PacketType) -> bool {
+        self.id().is_allowed_in(packet_type)
+    }
+
+    /// Decodes a standalone property block, made of its length followed by
+    /// the properties, out of `reader`. No check is made on whether the
+    /// properties are allowed in any specific packet, only unicity
+    /// requirements are enforced.
+    pub async fn decode_block<R: AsyncRead + Unpin>(reader: R) -> SageResult<Vec<Property>> {
+        Property::decode_block_with(reader, &DecodeOptions::default()).await
+    }
+
+    /// Decodes a standalone property block as `decode_block` does, applying
+    /// the restrictions given by `options`.
+    pub async fn decode_block_with<R: AsyncRead + Unpin>(
+        reader: R,
+        options: &DecodeOptions,
+    ) -> SageResult<Vec<Property>> {
+        let mut properties = Vec::new();
+        let mut decoder = PropertiesDecoder::take(reader, options).await?;
+        while decoder.has_properties() {
+            properties.push(decoder.read().await?);
+        }
+        Ok(properties)
+    }
+
+    /// Whether the property holds the value the specification assumes when
+    /// the property is absent. This is the single table of default values:
+    /// such properties are neither encoded nor counted by `encoded_len`.
+    pub(crate) fn is_default(&self) -> bool {
+        match self {
+            Property::PayloadFormatIndicator(v) => *v == DEFAULT_PAYLOAD_FORMAT_INDICATOR,
+            Property::RequestProblemInformation(v) => *v == DEFAULT_REQUEST_PROBLEM_INFORMATION,
+            Property::RequestResponseInformation(v) => *v == DEFAULT_REQUEST_RESPONSE_INFORMATION,
+            Property::RetainAvailable(v) => *v == DEFAULT_RETAIN_AVAILABLE,
+            Property::WildcardSubscriptionAvailable(v) => {
+                *v == DEFAULT_WILCARD_SUBSCRIPTION_AVAILABLE
+            }
+            Property::SharedSubscriptionAvailable(v) => *v == DEFAULT_SHARED_SUBSCRIPTION_AVAILABLE,
+            Property::SubscriptionIdentifiersAvailable(v) => {
+                *v == DEFAULT_SUBSCRIPTION_IDENTIFIER_AVAILABLE
+            }
+            Property::MaximumQoS(v) => *v == DEFAULT_MAXIMUM_QOS,
+            Property::SessionExpiryInterval(v) => *v == 0,
+            Property::WillDelayInterval(v) => *v == DEFAULT_WILL_DELAY_INTERVAL,
+            Property::ReceiveMaximum(v) => *v == DEFAULT_RECEIVE_MAXIMUM,
+            Property::TopicAliasMaximum(v) => *v == DEFAULT_TOPIC_ALIAS_MAXIMUM,
+            _ => false,
+        }
+    }
+
+    /// The number of bytes this property occupies once encoded, identifier
+    /// included. Properties holding their default value are not encoded and
+    /// thus have a length of `0`.
+    pub fn encoded_len(&self) -> usize {
+        if self.is_default() {
+            return 0;
+        }
+        let value_len = match self {
+            Property::PayloadFormatIndicator(_)
+            | Property::RequestProblemInformation(_)
+            | Property::RequestResponseInformation(_)
+            | Property::RetainAvailable(_)
+            | Property::WildcardSubscriptionAvailable(_)
+            | Property::SharedSubscriptionAvailable(_)
+            | Property::SubscriptionIdentifiersAvailable(_)
+            | Property::MaximumQoS(_) => 1,
+            Property::SessionExpiryInterval(_)
+            | Property::WillDelayInterval(_)
+            | Property::MessageExpiryInterval(_)
+            | Property::MaximumPacketSize(_) => 4,
+            Property::ReceiveMaximum(_)
+            | Property::TopicAliasMaximum(_)
+            | Property::ServerKeepAlive(_)
+            | Property::TopicAlias(_) => 2,
+            Property::SubscriptionIdentifier(v) => match v {
+                0..=127 => 1,
+                128..=16_383 => 2,
+                16_384..=2_097_151 => 3,
+                _ => 4,
+            },
+            Property::ContentType(v)
+            | Property::AssignedClientIdentifier(v)
+            | Property::AuthenticationMethod(v)
+            | Property::ResponseInformation(v)
+            | Property::ServerReference(v)
+            | Property::ReasonString(v) => 2 + v.len(),
+            Property::ResponseTopic(v) => 2 + v.to_string().len(),
+            Property::CorrelationData(v) | Property::AuthenticationData(v) => 2 + v.len(),
+            Property::UserProperty(k, v) => 4 + k.len() + v.len(),
+            Property::UserPropertyRaw(k, v) => 4 + k.len() + v.len(),
+        };
+        1 + value_len
+    }
+
+    pub(crate) async fn encode<W: AsyncWrite + Unpin>(self, writer: &mut W) -> SageResult<usize> {
+        if self.is_default() {
+            return Ok(0);
+        }
         match self {
             Property::PayloadFormatIndicator(v) => {
-                if v != DEFAULT_PAYLOAD_FORMAT_INDICATOR {
-                    let n_bytes =
-                        write_property_id(PropertyId::PayloadFormatIndicator, writer).await?;
-                    Ok(n_bytes + codec::write_bool(v, writer).await?)
-                } else {
-                    Ok(0)
-                }
+                let n_bytes = write_property_id(PropertyId::PayloadFormatIndicator, writer).await?;
+                Ok(n_bytes + codec::write_bool(v, writer).await?)
             }
             Property::MessageExpiryInterval(v) => {
                 let n_bytes = write_property_id(PropertyId::MessageExpiryInterval, writer).await?;
@@ -300,13 +693,8 @@ impl Property {
                 }
             }
             Property::SessionExpiryInterval(v) => {
-                if v != 0 {
-                    let n_bytes =
-                        write_property_id(PropertyId::SessionExpiryInterval, writer).await?;
-                    Ok(n_bytes + codec::write_four_byte_integer(v, writer).await?)
-                } else {
-                    Ok(0)
-                }
+                let n_bytes = write_property_id(PropertyId::SessionExpiryInterval, writer).await?;
+                Ok(n_bytes + codec::write_four_byte_integer(v, writer).await?)
             }
             Property::AssignedClientIdentifier(v) => {
                 let n_bytes =
@@ -326,30 +714,18 @@ impl Property {
                 Ok(n_bytes + codec::write_binary_data(&v, writer).await?)
             }
             Property::RequestProblemInformation(v) => {
-                if v != DEFAULT_REQUEST_PROBLEM_INFORMATION {
-                    let n_bytes =
-                        write_property_id(PropertyId::RequestProblemInformation, writer).await?;
-                    Ok(n_bytes + codec::write_bool(v, writer).await?)
-                } else {
-                    Ok(0)
-                }
+                let n_bytes =
+                    write_property_id(PropertyId::RequestProblemInformation, writer).await?;
+                Ok(n_bytes + codec::write_bool(v, writer).await?)
             }
             Property::WillDelayInterval(v) => {
-                if v != DEFAULT_WILL_DELAY_INTERVAL {
-                    let n_bytes = write_property_id(PropertyId::WillDelayInterval, writer).await?;
-                    Ok(n_bytes + codec::write_four_byte_integer(v, writer).await?)
-                } else {
-                    Ok(0)
-                }
+                let n_bytes = write_property_id(PropertyId::WillDelayInterval, writer).await?;
+                Ok(n_bytes + codec::write_four_byte_integer(v, writer).await?)
             }
             Property::RequestResponseInformation(v) => {
-                if v != DEFAULT_REQUEST_RESPONSE_INFORMATION {
-                    let n_bytes =
-                        write_property_id(PropertyId::RequestResponseInformation, writer).await?;
-                    Ok(n_bytes + codec::write_bool(v, writer).await?)
-                } else {
-                    Ok(0)
-                }
+                let n_bytes =
+                    write_property_id(PropertyId::RequestResponseInformation, writer).await?;
+                Ok(n_bytes + codec::write_bool(v, writer).await?)
             }
             Property::ResponseInformation(v) => {
                 let n_bytes = write_property_id(PropertyId::ResponseInformation, writer).await?;
@@ -365,57 +741,45 @@ impl Property {
             }
             Property::ReceiveMaximum(v) => match v {
                 0 => Err(MalformedPacket.into()),
-                DEFAULT_RECEIVE_MAXIMUM => Ok(0),
                 _ => {
                     let n_bytes = write_property_id(PropertyId::ReceiveMaximum, writer).await?;
                     Ok(n_bytes + codec::write_two_byte_integer(v, writer).await?)
                 }
             },
             Property::TopicAliasMaximum(v) => {
-                if v != DEFAULT_TOPIC_ALIAS_MAXIMUM {
-                    let n_bytes = write_property_id(PropertyId::TopicAliasMaximum, writer).await?;
-                    Ok(n_bytes + codec::write_two_byte_integer(v, writer).await?)
-                } else {
-                    Ok(0)
-                }
+                let n_bytes = write_property_id(PropertyId::TopicAliasMaximum, writer).await?;
+                Ok(n_bytes + codec::write_two_byte_integer(v, writer).await?)
             }
             Property::TopicAlias(v) => {
                 let n_bytes = write_property_id(PropertyId::TopicAlias, writer).await?;
                 Ok(n_bytes + codec::write_two_byte_integer(v, writer).await?)
             }
-            Property::MaximumQoS(v) => match v {
-                DEFAULT_MAXIMUM_QOS => Ok(0),
-                _ => {
-                    let n_bytes = write_property_id(PropertyId::MaximumQoS, writer).await?;
-                    Ok(n_bytes + codec::write_qos(v, writer).await?)
-                }
-            },
+            Property::MaximumQoS(v) => {
+                let n_bytes = write_property_id(PropertyId::MaximumQoS, writer).await?;
+                Ok(n_bytes + codec::write_qos(v, writer).await?)
+            }
             Property::RetainAvailable(v) => {
-                if v != DEFAULT_RETAIN_AVAILABLE {
-                    let n_bytes = write_property_id(PropertyId::RetainAvailable, writer).await?;
-                    Ok(n_bytes + codec::write_bool(v, writer).await?)
-                } else {
-                    Ok(0)
-                }
+                let n_bytes = write_property_id(PropertyId::RetainAvailable, writer).await?;
+                Ok(n_bytes + codec::write_bool(v, writer).await?)
             }
             Property::UserProperty(k, v) => {
                 let mut n_bytes = write_property_id(PropertyId::UserProperty, writer).await?;
                 n_bytes += codec::write_utf8_string(&k, writer).await?;
                 Ok(n_bytes + (codec::write_utf8_string(&v, writer).await?))
             }
+            Property::UserPropertyRaw(k, v) => {
+                let mut n_bytes = write_property_id(PropertyId::UserProperty, writer).await?;
+                n_bytes += codec::write_utf8_string(&k, writer).await?;
+                Ok(n_bytes + (codec::write_binary_data(&v, writer).await?))
+            }
             Property::MaximumPacketSize(v) => {
                 let n_bytes = write_property_id(PropertyId::MaximumPacketSize, writer).await?;
                 Ok(n_bytes + codec::write_four_byte_integer(v, writer).await?)
             }
             Property::WildcardSubscriptionAvailable(v) => {
-                if v != DEFAULT_WILCARD_SUBSCRIPTION_AVAILABLE {
-                    let n_bytes =
-                        write_property_id(PropertyId::WildcardSubscriptionAvailable, writer)
-                            .await?;
-                    Ok(n_bytes + codec::write_bool(v, writer).await?)
-                } else {
-                    Ok(0)
-                }
+                let n_bytes =
+                    write_property_id(PropertyId::WildcardSubscriptionAvailable, writer).await?;
+                Ok(n_bytes + codec::write_bool(v, writer).await?)
             }
             Property::SubscriptionIdentifiersAvailable(v) => {
                 let n_bytes =
@@ -423,14 +787,236 @@ impl Property {
                 Ok(n_bytes + codec::write_bool(v, writer).await?)
             }
             Property::SharedSubscriptionAvailable(v) => {
-                if v != DEFAULT_SHARED_SUBSCRIPTION_AVAILABLE {
-                    let n_bytes =
-                        write_property_id(PropertyId::SharedSubscriptionAvailable, writer).await?;
-                    Ok(n_bytes + codec::write_bool(v, writer).await?)
-                } else {
-                    Ok(0)
-                }
+                let n_bytes =
+                    write_property_id(PropertyId::SharedSubscriptionAvailable, writer).await?;
+                Ok(n_bytes + codec::write_bool(v, writer).await?)
             }
         }
     }
 }
+
+#[cfg(test)]
+mod unit {
+
+    use super::*;
+    use crate::Error;
+    use std::io::Cursor;
+
+    fn user_properties() -> Vec<u8> {
+        vec![
+            22, 0x26, 0, 3, 107, 101, 121, 0, 3, 118, 97, 108, 0x26, 0, 3, 107, 101, 121, 0, 3,
+            118, 97, 108,
+        ]
+    }
+
+    #[tokio::test]
+    async fn saw_explicit_defaults() {
+        // TopicAliasMaximum(5) then RequestProblemInformation(true)
+        let mut test_stream = Cursor::new(vec![5, 0x22, 0, 5, 0x17, 1]);
+        let mut decoder = PropertiesDecoder::take(&mut test_stream, &Default::default())
+            .await
+            .unwrap();
+        assert!(matches!(
+            decoder.read().await,
+            Ok(Property::TopicAliasMaximum(5))
+        ));
+        assert!(!decoder.saw_explicit_defaults());
+        assert!(matches!(
+            decoder.read().await,
+            Ok(Property::RequestProblemInformation(true))
+        ));
+        assert!(decoder.saw_explicit_defaults());
+    }
+
+    #[tokio::test]
+    async fn string_limit() {
+        let mut test_stream = Cursor::new(user_properties());
+        let options = DecodeOptions {
+            string_limit: Some(8),
+            ..Default::default()
+        };
+        let mut decoder = PropertiesDecoder::take(&mut test_stream, &options)
+            .await
+            .unwrap();
+        assert!(decoder.read().await.is_ok());
+        assert!(matches!(
+            decoder.read().await,
+            Err(Error::Reason(ProtocolError))
+        ));
+    }
+
+    #[tokio::test]
+    async fn string_limit_not_exceeded() {
+        let mut test_stream = Cursor::new(user_properties());
+        let options = DecodeOptions {
+            string_limit: Some(12),
+            ..Default::default()
+        };
+        let mut decoder = PropertiesDecoder::take(&mut test_stream, &options)
+            .await
+            .unwrap();
+        while decoder.has_properties() {
+            assert!(decoder.read().await.is_ok());
+        }
+    }
+
+    fn invalid_utf8_user_property() -> Vec<u8> {
+        vec![8, 0x26, 0, 1, 107, 0, 2, 0xFF, 0xFE]
+    }
+
+    #[tokio::test]
+    async fn user_property_invalid_utf8() {
+        let mut test_stream = Cursor::new(invalid_utf8_user_property());
+        let mut decoder = PropertiesDecoder::take(&mut test_stream, &Default::default())
+            .await
+            .unwrap();
+        assert!(decoder.read().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn user_property_invalid_utf8_lenient() {
+        let mut test_stream = Cursor::new(invalid_utf8_user_property());
+        let options = DecodeOptions {
+            lenient_user_properties: true,
+            ..Default::default()
+        };
+        let mut decoder = PropertiesDecoder::take(&mut test_stream, &options)
+            .await
+            .unwrap();
+        let property = decoder.read().await.unwrap();
+        assert_eq!(
+            property,
+            Property::UserPropertyRaw("k".into(), vec![0xFF, 0xFE])
+        );
+
+        // Encoded back as the same bytes
+        let mut encoded = Vec::new();
+        property.encode(&mut encoded).await.unwrap();
+        assert_eq!(encoded, invalid_utf8_user_property()[1..]);
+    }
+
+    #[test]
+    fn encoded_len_default() {
+        assert_eq!(
+            Property::RetainAvailable(DEFAULT_RETAIN_AVAILABLE).encoded_len(),
+            0
+        );
+    }
+
+    #[tokio::test]
+    async fn defaults_not_encoded() {
+        let defaults = vec![
+            Property::PayloadFormatIndicator(DEFAULT_PAYLOAD_FORMAT_INDICATOR),
+            Property::RequestProblemInformation(DEFAULT_REQUEST_PROBLEM_INFORMATION),
+            Property::RequestResponseInformation(DEFAULT_REQUEST_RESPONSE_INFORMATION),
+            Property::RetainAvailable(DEFAULT_RETAIN_AVAILABLE),
+            Property::WildcardSubscriptionAvailable(DEFAULT_WILCARD_SUBSCRIPTION_AVAILABLE),
+            Property::SharedSubscriptionAvailable(DEFAULT_SHARED_SUBSCRIPTION_AVAILABLE),
+            Property::SubscriptionIdentifiersAvailable(DEFAULT_SUBSCRIPTION_IDENTIFIER_AVAILABLE),
+            Property::MaximumQoS(DEFAULT_MAXIMUM_QOS),
+            Property::SessionExpiryInterval(0),
+            Property::WillDelayInterval(DEFAULT_WILL_DELAY_INTERVAL),
+            Property::ReceiveMaximum(DEFAULT_RECEIVE_MAXIMUM),
+            Property::TopicAliasMaximum(DEFAULT_TOPIC_ALIAS_MAXIMUM),
+        ];
+        for property in defaults {
+            assert!(property.is_default());
+            assert_eq!(property.encoded_len(), 0);
+            let mut encoded = Vec::new();
+            assert_eq!(property.encode(&mut encoded).await.unwrap(), 0);
+            assert!(encoded.is_empty());
+        }
+
+        let property = Property::SubscriptionIdentifiersAvailable(false);
+        let mut encoded = Vec::new();
+        let n_bytes = property.clone().encode(&mut encoded).await.unwrap();
+        assert_eq!(n_bytes, 2);
+        assert_eq!(property.encoded_len(), n_bytes);
+    }
+
+    #[tokio::test]
+    async fn encoded_len_topic_alias() {
+        let property = Property::TopicAlias(42);
+        assert_eq!(property.encoded_len(), 3);
+        let mut result = Vec::new();
+        assert_eq!(property.encode(&mut result).await.unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn decode_block() {
+        let mut test_stream = Cursor::new(vec![
+            28, 0x03, 0, 3, 116, 120, 116, // ContentType: txt
+            0x26, 0, 3, 107, 101, 121, 0, 3, 118, 97, 108, // key: val
+            0x26, 0, 3, 107, 101, 121, 0, 3, 118, 97, 108, // key: val
+        ]);
+        assert_eq!(
+            Property::decode_block(&mut test_stream).await.unwrap(),
+            vec![
+                Property::ContentType("txt".into()),
+                Property::UserProperty("key".into(), "val".into()),
+                Property::UserProperty("key".into(), "val".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn allowed_in() {
+        use PacketType::*;
+        let publish = Publish {
+            duplicate: false,
+            qos: QoS::AtMostOnce,
+            retain: false,
+        };
+        let user_property = Property::UserProperty("key".into(), "val".into());
+        for (property, packet_type, allowed) in vec![
+            (Property::TopicAlias(1), publish, true),
+            (Property::TopicAlias(1), Connect, false),
+            (Property::SubscriptionIdentifier(1), Subscribe, true),
+            (Property::SubscriptionIdentifier(1), ConnAck, false),
+            (Property::SessionExpiryInterval(1), Disconnect, true),
+            (Property::SessionExpiryInterval(1), publish, false),
+            (Property::ReasonString("".into()), PubAck, true),
+            (Property::ReasonString("".into()), Connect, false),
+            (Property::MaximumQoS(QoS::AtMostOnce), ConnAck, true),
+            (Property::MaximumQoS(QoS::AtMostOnce), Connect, false),
+            (Property::WillDelayInterval(1), Connect, true),
+            (Property::AuthenticationMethod("".into()), Auth, true),
+            (Property::AuthenticationMethod("".into()), Disconnect, false),
+            (user_property.clone(), UnSubscribe, true),
+            (user_property, PingReq, false),
+        ] {
+            assert_eq!(
+                property.is_allowed_in(packet_type),
+                allowed,
+                "{:?}",
+                property
+            );
+        }
+    }
+
+    #[test]
+    fn equal_unordered() {
+        let a = vec![
+            Property::TopicAlias(42),
+            Property::UserProperty("Mogwaï".into(), "Cat".into()),
+            Property::UserProperty("Mogwaï".into(), "Cat".into()),
+            Property::ContentType("text/plain".into()),
+        ];
+        let b = vec![
+            Property::UserProperty("Mogwaï".into(), "Cat".into()),
+            Property::ContentType("text/plain".into()),
+            Property::UserProperty("Mogwaï".into(), "Cat".into()),
+            Property::TopicAlias(42),
+        ];
+        assert!(properties_equal_unordered(&a, &b));
+
+        let c = vec![
+            Property::UserProperty("Mogwaï".into(), "Cat".into()),
+            Property::ContentType("text/plain".into()),
+            Property::TopicAlias(42),
+            Property::TopicAlias(42),
+        ];
+        assert!(!properties_equal_unordered(&a, &c));
+        assert!(!properties_equal_unordered(&a, &b[1..]));
+    }
+}
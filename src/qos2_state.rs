@@ -0,0 +1,99 @@
+use crate::{PacketType, QoS, ReasonCode::ProtocolError, Result as SageResult};
+
+/// The state of an `ExactlyOnce` delivery. The handshake is made of four
+/// packets which must be exchanged in order: `Publish`, `PubRec`, `PubRel`
+/// and `PubComp`.
+/// The same state machine is used by both sides of the exchange, the sender
+/// and the receiver of the message.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum Qos2State {
+    /// No packet has been exchanged yet. Expecting a `Publish` packet.
+    #[default]
+    Idle,
+
+    /// The `Publish` packet was exchanged. Expecting a `PubRec` packet.
+    Published,
+
+    /// The `PubRec` packet was exchanged. Expecting a `PubRel` packet.
+    Received,
+
+    /// The `PubRel` packet was exchanged. Expecting a `PubComp` packet.
+    Released,
+
+    /// The `PubComp` packet was exchanged. The delivery is complete and no
+    /// more packet is expected.
+    Completed,
+}
+
+impl Qos2State {
+    /// Advances the handshake given the type of the next exchanged packet.
+    /// If the packet is not the one expected by the current state,
+    /// `ProtocolError` is returned and the state is left untouched.
+    pub fn step(&mut self, packet_type: PacketType) -> SageResult<()> {
+        *self = match (*self, packet_type) {
+            (
+                Qos2State::Idle,
+                PacketType::Publish {
+                    qos: QoS::ExactlyOnce,
+                    ..
+                },
+            ) => Qos2State::Published,
+            (Qos2State::Published, PacketType::PubRec) => Qos2State::Received,
+            (Qos2State::Received, PacketType::PubRel) => Qos2State::Released,
+            (Qos2State::Released, PacketType::PubComp) => Qos2State::Completed,
+            _ => return Err(ProtocolError.into()),
+        };
+        Ok(())
+    }
+
+    /// Whether the handshake is complete.
+    pub fn is_completed(&self) -> bool {
+        *self == Qos2State::Completed
+    }
+}
+
+#[cfg(test)]
+mod unit {
+
+    use super::*;
+    use crate::Error;
+
+    fn publish(qos: QoS) -> PacketType {
+        PacketType::Publish {
+            duplicate: false,
+            qos,
+            retain: false,
+        }
+    }
+
+    #[test]
+    fn valid_sequence() {
+        let mut state = Qos2State::default();
+        assert!(state.step(publish(QoS::ExactlyOnce)).is_ok());
+        assert!(state.step(PacketType::PubRec).is_ok());
+        assert!(state.step(PacketType::PubRel).is_ok());
+        assert!(state.step(PacketType::PubComp).is_ok());
+        assert!(state.is_completed());
+    }
+
+    #[test]
+    fn pubcomp_before_pubrel() {
+        let mut state = Qos2State::default();
+        assert!(state.step(publish(QoS::ExactlyOnce)).is_ok());
+        assert!(state.step(PacketType::PubRec).is_ok());
+        assert!(matches!(
+            state.step(PacketType::PubComp),
+            Err(Error::Reason(ProtocolError))
+        ));
+        assert_eq!(state, Qos2State::Received);
+    }
+
+    #[test]
+    fn publish_with_lower_qos() {
+        let mut state = Qos2State::default();
+        assert!(matches!(
+            state.step(publish(QoS::AtLeastOnce)),
+            Err(Error::Reason(ProtocolError))
+        ));
+    }
+}
@@ -1,8 +1,9 @@
 use crate::{Error as SageError, ReasonCode::MalformedPacket};
-use std::convert::TryFrom;
+use std::{convert::TryFrom, fmt};
 
 /// Description the quality of service used in message publishing.
-#[derive(Debug, PartialEq, Clone, Copy, Eq)]
+/// Qualities of service are ordered from `AtMostOnce` to `ExactlyOnce`.
+#[derive(Debug, PartialEq, Clone, Copy, Eq, PartialOrd, Ord)]
 pub enum QoS {
     /// The message is delivered according to the capabilities of the
     /// underlying network. No response is sent by the receiver and no retry is
@@ -35,3 +36,35 @@ impl TryFrom<u8> for QoS {
         }
     }
 }
+
+impl QoS {
+    /// The name of the quality of service, such as `"AtLeastOnce"`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QoS::AtMostOnce => "AtMostOnce",
+            QoS::AtLeastOnce => "AtLeastOnce",
+            QoS::ExactlyOnce => "ExactlyOnce",
+        }
+    }
+}
+
+/// Displays the quality of service in its numeric form, as used in the
+/// specification: `"0"`, `"1"` or `"2"`. Use `as_str` for its name.
+impl fmt::Display for QoS {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "{}", *self as u8)
+    }
+}
+
+#[cfg(test)]
+mod unit {
+    use super::*;
+
+    #[test]
+    fn display() {
+        assert_eq!(format!("{}", QoS::AtMostOnce), "0");
+        assert_eq!(format!("{}", QoS::AtLeastOnce), "1");
+        assert_eq!(format!("{}", QoS::ExactlyOnce), "2");
+        assert_eq!(QoS::AtLeastOnce.as_str(), "AtLeastOnce");
+    }
+}
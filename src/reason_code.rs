@@ -1,9 +1,9 @@
-use crate::Error as SageError;
-use std::{convert::TryFrom, io::ErrorKind};
+use crate::{Disconnect, Error as SageError, PacketType, QoS, Result as SageResult};
+use std::{cmp::Ordering, convert::TryFrom, io::ErrorKind};
 
 /// A `ReasonCode` is an identifier describing a response in any ackowledgement
 /// packet (such as `Connack` or `SubAck`)
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ReasonCode {
     /// Generic success reason code indicating an operation performed well.
     /// According to the emmiting packet, the following meanings are applied:
@@ -146,14 +146,121 @@ pub enum ReasonCode {
     WildcardSubscriptionsNotSupported = 0xA2,
 }
 
+/// The severity of a reason code, as returned by `ReasonCode::log_level`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    /// The operation succeeded.
+    Info,
+
+    /// The operation failed because of the peer, such as an invalid packet
+    /// or a denied permission.
+    Warn,
+
+    /// The operation failed because of the server itself, such as an
+    /// internal error or a lack of availability.
+    Error,
+}
+
+impl ReasonCode {
+    /// The byte value of the reason code, as encoded in packets.
+    pub fn as_u8(&self) -> u8 {
+        *self as u8
+    }
+
+    /// Whether the reason code indicates a failure, which is the case for all
+    /// values of 0x80 or above.
+    pub fn is_error(&self) -> bool {
+        self.as_u8() >= 0x80
+    }
+
+    /// The severity of the reason code, for logging purposes:
+    /// - `Info` for every success reason code (below 0x80)
+    /// - `Error` for failures of the server itself: `UnspecifiedError`,
+    ///   `ImplementationSpecificError`, `ServerUnavailable`, `ServerBusy`,
+    ///   `ServerShuttingDown` and `AdministrativeAction`
+    /// - `Warn` for every other failure, which are caused by the peer
+    pub fn log_level(&self) -> LogLevel {
+        match self {
+            _ if !self.is_error() => LogLevel::Info,
+            ReasonCode::UnspecifiedError
+            | ReasonCode::ImplementationSpecificError
+            | ReasonCode::ServerUnavailable
+            | ReasonCode::ServerBusy
+            | ReasonCode::ServerShuttingDown
+            | ReasonCode::AdministrativeAction => LogLevel::Error,
+            _ => LogLevel::Warn,
+        }
+    }
+
+    /// Parses the given byte as a reason code carried by a packet of type
+    /// `packet_type`. Fails with `ProtocolError` if the byte is not a reason
+    /// code or if the reason code cannot be used in such a packet.
+    pub fn try_parse(byte: u8, packet_type: PacketType) -> SageResult<Self> {
+        let reason_code = ReasonCode::try_from(byte)?;
+        let valid = match packet_type {
+            PacketType::ConnAck => matches!(
+                byte,
+                0x00 | 0x80..=0x8A | 0x8C | 0x90 | 0x95 | 0x97 | 0x99..=0x9D | 0x9F
+            ),
+            PacketType::PubAck | PacketType::PubRec => matches!(
+                byte,
+                0x00 | 0x10 | 0x80 | 0x83 | 0x87 | 0x90 | 0x91 | 0x97 | 0x99
+            ),
+            PacketType::PubRel | PacketType::PubComp => matches!(byte, 0x00 | 0x92),
+            PacketType::SubAck => matches!(
+                byte,
+                0x00..=0x02 | 0x80 | 0x83 | 0x87 | 0x8F | 0x91 | 0x97 | 0x9E | 0xA1 | 0xA2
+            ),
+            PacketType::UnSubAck => {
+                matches!(byte, 0x00 | 0x11 | 0x80 | 0x83 | 0x87 | 0x8F | 0x91)
+            }
+            PacketType::Disconnect => Disconnect::is_valid_reason_code(reason_code),
+            PacketType::Auth => matches!(byte, 0x00 | 0x18 | 0x19),
+            _ => false,
+        };
+        if valid {
+            Ok(reason_code)
+        } else {
+            Err(ReasonCode::ProtocolError.into())
+        }
+    }
+}
+
+/// Reason codes are ordered by their byte value. This ordering has no meaning
+/// in the protocol and is only provided for sorting purposes.
+impl Ord for ReasonCode {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_u8().cmp(&other.as_u8())
+    }
+}
+
+impl PartialOrd for ReasonCode {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl From<SageError> for ReasonCode {
     fn from(e: SageError) -> Self {
         match e {
-            SageError::Reason(rc) => rc,
+            SageError::Reason(rc) | SageError::Context(rc, _) => rc,
             SageError::Io(e) => match e.kind() {
                 ErrorKind::UnexpectedEof => ReasonCode::ProtocolError,
                 _ => ReasonCode::MalformedPacket,
             },
+            SageError::AtOffset(e, _) => (*e).into(),
+        }
+    }
+}
+
+/// The reason code granting a subscription with the given quality of
+/// service in a `SubAck` packet. Granted QoS 0 is `Success`.
+impl From<QoS> for ReasonCode {
+    fn from(qos: QoS) -> Self {
+        match qos {
+            QoS::AtMostOnce => ReasonCode::Success,
+            QoS::AtLeastOnce => ReasonCode::GrantedQoS1,
+            QoS::ExactlyOnce => ReasonCode::GrantedQoS2,
         }
     }
 }
@@ -210,3 +317,46 @@ impl TryFrom<u8> for ReasonCode {
         }
     }
 }
+
+#[cfg(test)]
+mod unit {
+
+    use super::*;
+
+    #[test]
+    fn ordering() {
+        assert!(ReasonCode::Success < ReasonCode::Banned);
+        let mut reason_codes = vec![
+            ReasonCode::Banned,
+            ReasonCode::GrantedQoS2,
+            ReasonCode::Success,
+        ];
+        reason_codes.sort();
+        assert_eq!(
+            reason_codes,
+            vec![
+                ReasonCode::Success,
+                ReasonCode::GrantedQoS2,
+                ReasonCode::Banned
+            ]
+        );
+    }
+
+    #[test]
+    fn from_qos() {
+        assert_eq!(ReasonCode::from(QoS::AtMostOnce), ReasonCode::Success);
+        assert_eq!(ReasonCode::from(QoS::AtLeastOnce), ReasonCode::GrantedQoS1);
+        assert_eq!(ReasonCode::from(QoS::ExactlyOnce), ReasonCode::GrantedQoS2);
+    }
+
+    #[test]
+    fn log_level() {
+        assert_eq!(ReasonCode::Success.log_level(), LogLevel::Info);
+        assert_eq!(
+            ReasonCode::NoMatchingSubscribers.log_level(),
+            LogLevel::Info
+        );
+        assert_eq!(ReasonCode::NotAuthorized.log_level(), LogLevel::Warn);
+        assert_eq!(ReasonCode::ServerUnavailable.log_level(), LogLevel::Error);
+    }
+}
@@ -0,0 +1,90 @@
+use crate::defaults::DEFAULT_RECEIVE_MAXIMUM;
+
+/// Both clients and servers advertise a receive maximum upon connection,
+/// which is the number of `AtLeastOnce` and `ExactlyOnce` `Publish` packets
+/// they are willing to process concurrently.
+/// A `ReceiveQuota` tracks the outstanding (sent but not acknowledged yet)
+/// publish packets for one direction of a connection. Before sending a QoS 1
+/// or QoS 2 `Publish` a quota must be acquired, and it is released once the
+/// `PubAck` or `PubComp` is received.
+/// `AtMostOnce` publish packets are not subject to flow control.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReceiveQuota {
+    maximum: u16,
+    in_flight: u16,
+}
+
+impl Default for ReceiveQuota {
+    fn default() -> Self {
+        ReceiveQuota::new(DEFAULT_RECEIVE_MAXIMUM)
+    }
+}
+
+impl ReceiveQuota {
+    /// Creates a new quota allowing at most `maximum` publish packets in
+    /// flight, usually the `receive_maximum` value of the peer's `Connect` or
+    /// `ConnAck` packet.
+    pub fn new(maximum: u16) -> Self {
+        ReceiveQuota {
+            maximum,
+            in_flight: 0,
+        }
+    }
+
+    /// Tries to reserve a slot for a new publish packet. Returns `false` if
+    /// the quota is exhausted, in which case the packet must not be sent.
+    pub fn try_acquire(&mut self) -> bool {
+        if self.in_flight < self.maximum {
+            self.in_flight += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Releases a slot once a publish packet has been acknowledged.
+    /// Releasing a quota with no publish in flight has no effect.
+    pub fn release(&mut self) {
+        self.in_flight = self.in_flight.saturating_sub(1);
+    }
+
+    /// The number of publish packets currently in flight.
+    pub fn in_flight(&self) -> u16 {
+        self.in_flight
+    }
+}
+
+#[cfg(test)]
+mod unit {
+
+    use super::*;
+
+    #[test]
+    fn acquire_up_to_maximum() {
+        let mut quota = ReceiveQuota::new(3);
+        assert!(quota.try_acquire());
+        assert!(quota.try_acquire());
+        assert!(quota.try_acquire());
+        assert!(!quota.try_acquire());
+        assert_eq!(quota.in_flight(), 3);
+    }
+
+    #[test]
+    fn release() {
+        let mut quota = ReceiveQuota::new(1);
+        assert!(quota.try_acquire());
+        assert!(!quota.try_acquire());
+        quota.release();
+        assert_eq!(quota.in_flight(), 0);
+        assert!(quota.try_acquire());
+    }
+
+    #[test]
+    fn release_when_empty() {
+        let mut quota = ReceiveQuota::new(1);
+        quota.release();
+        assert_eq!(quota.in_flight(), 0);
+        assert!(quota.try_acquire());
+        assert!(!quota.try_acquire());
+    }
+}
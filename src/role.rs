@@ -0,0 +1,10 @@
+/// The side of an MQTT connection an endpoint plays. Some rules of the
+/// protocol depend on whether packets are sent by the client or the server.
+#[derive(Debug, PartialEq, Clone, Copy, Eq)]
+pub enum Role {
+    /// The endpoint opening the connection with a `Connect` packet.
+    Client,
+
+    /// The endpoint accepting the connection with a `ConnAck` packet.
+    Server,
+}
@@ -1,4 +1,4 @@
-use std::fmt;
+use std::{borrow::Cow, fmt};
 
 const LEVEL_SEPARATOR: char = '/';
 
@@ -33,13 +33,13 @@ impl fmt::Display for Topic {
             self.spec
                 .iter()
                 .map(|l| match l {
-                    TopicLevel::Empty => "",
-                    TopicLevel::Name(s) => s.as_ref(),
-                    TopicLevel::Share(_) => "Shareuh",
-                    TopicLevel::Any => "+",
-                    TopicLevel::MultipleAny => "#",
+                    TopicLevel::Empty => "".into(),
+                    TopicLevel::Name(s) => s.into(),
+                    TopicLevel::Share(s) => format!("$share/{}", s).into(),
+                    TopicLevel::Any => "+".into(),
+                    TopicLevel::MultipleAny => "#".into(),
                 })
-                .collect::<Vec<&str>>()
+                .collect::<Vec<Cow<str>>>()
                 .join("/")
         )
     }
@@ -89,7 +89,10 @@ impl From<&str> for Topic {
 impl Topic {
     /// Returns the name of the share if any
     pub fn share(&self) -> Option<String> {
-        None
+        match self.spec.first() {
+            Some(TopicLevel::Share(s)) => Some(s.clone()),
+            _ => None,
+        }
     }
 
     /// Checks whether the topic contains any wildcard
@@ -98,6 +101,71 @@ impl Topic {
             .iter()
             .any(|l| matches!(l, TopicLevel::Any | TopicLevel::MultipleAny))
     }
+
+    /// Uses the topic, seen as a topic filter, as a topic name. This is only
+    /// possible for exact filters, so `None` is returned if the topic
+    /// contains wildcards or is a shared subscription.
+    pub fn as_topic_name(&self) -> Option<Topic> {
+        match self.spec.first() {
+            Some(TopicLevel::Share(_)) => None,
+            _ if self.has_wildcards() => None,
+            _ => Some(self.clone()),
+        }
+    }
+
+    /// Checks whether every topic name matched by `other` is also matched by
+    /// `self`, meaning a subscription to `other` is redundant with one to
+    /// `self`. For example `a/#` covers `a/b` and `a/+/c`, `a/+` covers `a/b`
+    /// but not `a/b/c`, and any topic covers itself.
+    /// The share name of shared subscriptions is ignored, only the filters
+    /// are compared.
+    pub fn covers(&self, other: &Topic) -> bool {
+        let lhs = self.filter_levels();
+        let rhs = other.filter_levels();
+
+        // Wildcards at the first level do not match topics starting with `$`
+        if let (Some(TopicLevel::Any | TopicLevel::MultipleAny), Some(TopicLevel::Name(name))) =
+            (lhs.first(), rhs.first())
+        {
+            if name.starts_with('$') {
+                return false;
+            }
+        }
+
+        Topic::levels_cover(lhs, rhs)
+    }
+
+    /// Compares two topics level by level. Empty levels are significant in
+    /// MQTT, therefore `a/b` and `a/b/` are different topics, and so are `a`
+    /// and `/a`. This is the same comparison as `==`, spelled out for call
+    /// sites which want to make it explicit that no normalization happens.
+    pub fn eq_strict(&self, other: &Topic) -> bool {
+        self.spec == other.spec
+    }
+
+    /// Checks whether the topic is only made of empty levels, such as the
+    /// empty topic, `/` or `//`.
+    pub fn is_empty_levels_only(&self) -> bool {
+        self.spec.iter().all(|l| matches!(l, TopicLevel::Empty))
+    }
+
+    fn filter_levels(&self) -> &[TopicLevel] {
+        match self.spec.first() {
+            Some(TopicLevel::Share(_)) => &self.spec[1..],
+            _ => &self.spec,
+        }
+    }
+
+    fn levels_cover(lhs: &[TopicLevel], rhs: &[TopicLevel]) -> bool {
+        match (lhs.first(), rhs.first()) {
+            (Some(TopicLevel::MultipleAny), _) => true,
+            (None, None) => true,
+            (None, Some(_)) | (Some(_), None) => false,
+            (Some(TopicLevel::Any), Some(TopicLevel::MultipleAny)) => false,
+            (Some(TopicLevel::Any), Some(_)) => Topic::levels_cover(&lhs[1..], &rhs[1..]),
+            (Some(l), Some(r)) => l == r && Topic::levels_cover(&lhs[1..], &rhs[1..]),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -162,6 +230,51 @@ mod unit {
         share_wildcard_pound_2: ("$share/#/#",             vec![Share("#".into()), MultipleAny], ),
     }
 
+    #[test]
+    fn covers_itself() {
+        for topic in &[
+            "a",
+            "a/b",
+            "/a/",
+            "a/+/c",
+            "a/#",
+            "+",
+            "#",
+            "$share/group/a/b",
+        ] {
+            let topic = Topic::from(*topic);
+            assert!(topic.covers(&topic));
+        }
+    }
+
+    #[test]
+    fn multiple_any_covers() {
+        let filter = Topic::from("a/#");
+        assert!(filter.covers(&Topic::from("a")));
+        assert!(filter.covers(&Topic::from("a/b")));
+        assert!(filter.covers(&Topic::from("a/b/c")));
+        assert!(filter.covers(&Topic::from("a/+/c")));
+        assert!(!filter.covers(&Topic::from("b/c")));
+        assert!(!Topic::from("a/b").covers(&filter));
+    }
+
+    #[test]
+    fn any_covers() {
+        let filter = Topic::from("a/+");
+        assert!(filter.covers(&Topic::from("a/b")));
+        assert!(filter.covers(&Topic::from("a/")));
+        assert!(!filter.covers(&Topic::from("a/b/c")));
+        assert!(!filter.covers(&Topic::from("a/#")));
+        assert!(!Topic::from("a/b").covers(&filter));
+    }
+
+    #[test]
+    fn wildcards_do_not_cover_dollar_topics() {
+        assert!(!Topic::from("#").covers(&Topic::from("$SYS/uptime")));
+        assert!(!Topic::from("+/uptime").covers(&Topic::from("$SYS/uptime")));
+        assert!(Topic::from("$SYS/#").covers(&Topic::from("$SYS/uptime")));
+    }
+
     #[test]
     fn default_is_empty() {
         assert_eq!(
@@ -171,4 +284,33 @@ mod unit {
             },
         );
     }
+
+    #[test]
+    fn trailing_empty_level_is_significant() {
+        let topic = Topic::from("a/b");
+        let trailing = Topic::from("a/b/");
+        assert!(!topic.eq_strict(&trailing));
+        assert_ne!(topic, trailing);
+        assert!(topic.eq_strict(&Topic::from("a/b")));
+    }
+
+    #[test]
+    fn is_empty_levels_only() {
+        assert!(Topic::default().is_empty_levels_only());
+        assert!(Topic::from("/").is_empty_levels_only());
+        assert!(Topic::from("//").is_empty_levels_only());
+        assert!(!Topic::from("a/").is_empty_levels_only());
+        assert!(!Topic::from("$share//").is_empty_levels_only());
+    }
+
+    #[test]
+    fn as_topic_name() {
+        assert_eq!(
+            Topic::from("sensors/temperature").as_topic_name(),
+            Some(Topic::from("sensors/temperature"))
+        );
+        assert_eq!(Topic::from("sensors/+").as_topic_name(), None);
+        assert_eq!(Topic::from("sensors/#").as_topic_name(), None);
+        assert_eq!(Topic::from("$share/group/sensors").as_topic_name(), None);
+    }
 }
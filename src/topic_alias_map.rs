@@ -0,0 +1,119 @@
+use crate::{
+    Publish,
+    ReasonCode::{ProtocolError, TopicAliasInvalid},
+    Result as SageResult, Topic,
+};
+use std::collections::HashMap;
+
+/// Topic aliases let a `Publish` packet replace its topic name by a two bytes
+/// integer. Each direction of a connection has its own alias space, whose
+/// size is the `topic_alias_maximum` advertised by the receiver upon
+/// connection.
+/// A `TopicAliasMap` keeps track of the aliases received on a connection.
+/// A maximum of `0` means the receiver does not accept topic aliases at all.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TopicAliasMap {
+    maximum: u16,
+    aliases: HashMap<u16, Topic>,
+}
+
+impl TopicAliasMap {
+    /// Creates an empty map accepting aliases from `1` to `maximum`.
+    pub fn new(maximum: u16) -> Self {
+        TopicAliasMap {
+            maximum,
+            aliases: Default::default(),
+        }
+    }
+
+    fn check(&self, alias: u16) -> SageResult<()> {
+        if alias == 0 || alias > self.maximum {
+            Err(TopicAliasInvalid.into())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Sets or replaces the topic `alias` stands for. Fails with
+    /// `TopicAliasInvalid` if the alias is `0` or above the maximum.
+    pub fn register(&mut self, alias: u16, topic: Topic) -> SageResult<()> {
+        self.check(alias)?;
+        self.aliases.insert(alias, topic);
+        Ok(())
+    }
+
+    /// Returns the topic `alias` stands for. Fails with `TopicAliasInvalid`
+    /// if the alias is `0` or above the maximum, and with `ProtocolError` if
+    /// it was never registered.
+    pub fn resolve(&self, alias: u16) -> SageResult<&Topic> {
+        self.check(alias)?;
+        self.aliases.get(&alias).ok_or_else(|| ProtocolError.into())
+    }
+
+    /// Updates the map with a received `Publish` packet. If the packet has
+    /// both a topic name and a topic alias, the alias is registered. If it
+    /// only has a topic alias, its topic name is set from the map.
+    pub fn apply(&mut self, publish: &mut Publish) -> SageResult<()> {
+        if let Some(alias) = publish.topic_alias {
+            if publish.topic_name == Topic::default() {
+                publish.topic_name = self.resolve(alias)?.clone();
+            } else {
+                self.register(alias, publish.topic_name.clone())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod unit {
+
+    use super::*;
+    use crate::Error;
+
+    #[test]
+    fn register_and_resolve() {
+        let mut map = TopicAliasMap::new(10);
+        map.register(1, Topic::from("sage/mqtt")).unwrap();
+        assert_eq!(map.resolve(1).unwrap(), &Topic::from("sage/mqtt"));
+        assert!(matches!(map.resolve(2), Err(Error::Reason(ProtocolError))));
+        assert!(matches!(
+            map.register(11, Topic::from("sage/mqtt")),
+            Err(Error::Reason(TopicAliasInvalid))
+        ));
+    }
+
+    #[test]
+    fn aliases_disallowed() {
+        let mut map = TopicAliasMap::new(0);
+        let mut publish = Publish {
+            topic_alias: Some(1),
+            ..Publish::new("sage/mqtt", "Jaden")
+        };
+        assert!(matches!(
+            map.apply(&mut publish),
+            Err(Error::Reason(TopicAliasInvalid))
+        ));
+        assert!(matches!(
+            map.resolve(1),
+            Err(Error::Reason(TopicAliasInvalid))
+        ));
+    }
+
+    #[test]
+    fn apply() {
+        let mut map = TopicAliasMap::new(10);
+        let mut publish = Publish {
+            topic_alias: Some(1),
+            ..Publish::new("sage/mqtt", "Jaden")
+        };
+        map.apply(&mut publish).unwrap();
+
+        let mut publish = Publish {
+            topic_alias: Some(1),
+            ..Publish::new(Topic::default(), "Jarod")
+        };
+        map.apply(&mut publish).unwrap();
+        assert_eq!(publish.topic_name, Topic::from("sage/mqtt"));
+    }
+}
@@ -0,0 +1,55 @@
+use std::collections::HashMap;
+
+/// Conversions between the user properties of a packet, stored as a list of
+/// key-value pairs, and a `HashMap`.
+/// User properties may hold the same key several times and their order is
+/// meaningful. Both are lost when converting to a `HashMap`, which is why
+/// these conversions are only provided as an opt-in for the simple cases
+/// where keys are known to be unique.
+pub trait UserProperties {
+    /// Builds a map out of the user properties. If a key appears several
+    /// times, the last value wins.
+    fn to_map(&self) -> HashMap<String, String>;
+
+    /// Builds user properties out of `map`, in no particular order.
+    fn from_map(map: HashMap<String, String>) -> Self;
+}
+
+impl UserProperties for Vec<(String, String)> {
+    fn to_map(&self) -> HashMap<String, String> {
+        self.iter().cloned().collect()
+    }
+
+    fn from_map(map: HashMap<String, String>) -> Self {
+        map.into_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod unit {
+
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let mut user_properties = vec![
+            ("Mogwaï".to_string(), "Cat".to_string()),
+            ("Willow".to_string(), "Jaden".to_string()),
+        ];
+        let mut tested_result = Vec::from_map(user_properties.to_map());
+        tested_result.sort();
+        user_properties.sort();
+        assert_eq!(tested_result, user_properties);
+    }
+
+    #[test]
+    fn duplicate_keys_collapse() {
+        let user_properties = vec![
+            ("Willow".to_string(), "Jaden".to_string()),
+            ("Willow".to_string(), "Jarod".to_string()),
+        ];
+        let map = user_properties.to_map();
+        assert_eq!(map.len(), 1);
+        assert_eq!(map["Willow"], "Jarod");
+    }
+}
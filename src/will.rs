@@ -1,6 +1,12 @@
 use crate::{
     defaults::{DEFAULT_PAYLOAD_FORMAT_INDICATOR, DEFAULT_WILL_DELAY_INTERVAL},
-    QoS, Topic,
+    BinaryData, Publish, QoS,
+    ReasonCode::{QoSNotSupported, RetainNotSupported},
+    Result as SageResult, Topic,
+};
+use std::{
+    convert::TryInto,
+    time::{Duration, Instant},
 };
 
 /// Due to the unstable nature of a connexion, the client can loose its
@@ -39,7 +45,7 @@ pub struct Will {
     pub response_topic: Option<Topic>,
 
     /// Optional correlation optionaly used if the Will message is a request.
-    pub correlation_data: Option<Vec<u8>>,
+    pub correlation_data: Option<BinaryData>,
 
     /// General purpose properties
     pub user_properties: Vec<(String, String)>,
@@ -68,4 +74,166 @@ impl Will {
             message: message.as_bytes().to_vec(),
         }
     }
+
+    /// The will delay interval as a `Duration`.
+    pub fn delay_duration(&self) -> Duration {
+        Duration::from_secs(self.delay_interval as u64)
+    }
+
+    /// Sets the will delay interval from a `Duration`. Fractions of seconds
+    /// are truncated and the value saturates at `u32::MAX` seconds.
+    pub fn set_delay_duration(&mut self, duration: Duration) {
+        self.delay_interval = duration.as_secs().try_into().unwrap_or(u32::MAX);
+    }
+
+    /// The instant at which the will message should be published, given the
+    /// instant the network connection was closed. This is
+    /// `disconnect_instant` delayed by `delay_interval` seconds.
+    pub fn publish_at(&self, disconnect_instant: Instant) -> Instant {
+        disconnect_instant + self.delay_duration()
+    }
+
+    /// The instant at which the will message should be published, as given by
+    /// `publish_at`, unless the session ends first. The session ends
+    /// `session_expiry_interval` seconds after `disconnect_instant`, in
+    /// which case the will is published at session end.
+    pub fn publish_at_with_session_expiry(
+        &self,
+        disconnect_instant: Instant,
+        session_expiry_interval: u32,
+    ) -> Instant {
+        let session_end = disconnect_instant + Duration::from_secs(session_expiry_interval as u64);
+        self.publish_at(disconnect_instant).min(session_end)
+    }
+
+    /// The message expiry interval as a `Duration`, if any.
+    pub fn message_expiry_duration(&self) -> Option<Duration> {
+        self.message_expiry_interval
+            .map(|v| Duration::from_secs(v as u64))
+    }
+
+    /// Sets the message expiry interval from a `Duration`. Fractions of
+    /// seconds are truncated and the value saturates at `u32::MAX` seconds.
+    pub fn set_message_expiry_duration(&mut self, duration: Option<Duration>) {
+        self.message_expiry_interval = duration.map(|d| d.as_secs().try_into().unwrap_or(u32::MAX));
+    }
+
+    /// Checks the quality of service of the will against the maximum `max`
+    /// supported by the server. Fails with `QoSNotSupported` if the will
+    /// requires a higher quality of service.
+    pub fn validate_qos(&self, max: QoS) -> SageResult<()> {
+        if self.qos > max {
+            Err(QoSNotSupported.into())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Converts the will into the `Publish` packet a server sends when the
+    /// will is triggered. `retain_available` tells whether the server supports
+    /// retained messages. If it does not and the will is to be retained, the
+    /// conversion fails with `RetainNotSupported`.
+    /// The packet identifier is left to `None` and must be assigned by the
+    /// sender for `AtLeastOnce` and `ExactlyOnce` qualities of service.
+    pub fn into_publish_checked(self, retain_available: bool) -> SageResult<Publish> {
+        if self.retain && !retain_available {
+            return Err(RetainNotSupported.into());
+        }
+        Ok(Publish {
+            qos: self.qos,
+            retain: self.retain,
+            topic_name: self.topic,
+            payload_format_indicator: self.payload_format_indicator,
+            message_expiry_interval: self.message_expiry_interval,
+            response_topic: self.response_topic,
+            correlation_data: self.correlation_data,
+            user_properties: self.user_properties,
+            content_type: self.content_type,
+            message: self.message,
+            ..Default::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod unit {
+
+    use super::*;
+    use crate::Error;
+
+    #[test]
+    fn delay_duration() {
+        let mut will = Will::with_message(Topic::from("clients/status"), "offline");
+        will.set_delay_duration(Duration::from_secs(600));
+        assert_eq!(will.delay_interval, 600);
+        assert_eq!(will.delay_duration(), Duration::from_secs(600));
+        will.set_delay_duration(Duration::from_secs(u64::MAX));
+        assert_eq!(will.delay_interval, u32::MAX);
+    }
+
+    #[test]
+    fn publish_at() {
+        let will = Will {
+            delay_interval: 10,
+            ..Will::with_message(Topic::from("clients/status"), "offline")
+        };
+        let disconnect_instant = Instant::now();
+        assert_eq!(
+            will.publish_at(disconnect_instant),
+            disconnect_instant + Duration::from_secs(10)
+        );
+        assert_eq!(
+            will.publish_at_with_session_expiry(disconnect_instant, 60),
+            disconnect_instant + Duration::from_secs(10)
+        );
+        assert_eq!(
+            will.publish_at_with_session_expiry(disconnect_instant, 5),
+            disconnect_instant + Duration::from_secs(5)
+        );
+    }
+
+    #[test]
+    fn validate_qos() {
+        let will = Will {
+            qos: QoS::ExactlyOnce,
+            ..Will::with_message(Topic::from("clients/status"), "offline")
+        };
+        assert!(matches!(
+            will.validate_qos(QoS::AtLeastOnce),
+            Err(Error::Reason(QoSNotSupported))
+        ));
+        assert!(will.validate_qos(QoS::ExactlyOnce).is_ok());
+    }
+
+    #[test]
+    fn into_publish_retained() {
+        let will = Will {
+            qos: QoS::AtLeastOnce,
+            retain: true,
+            ..Will::with_message(Topic::from("clients/status"), "offline")
+        };
+        let publish = will.into_publish_checked(true).unwrap();
+        assert!(publish.retain);
+        assert_eq!(publish.qos, QoS::AtLeastOnce);
+        assert_eq!(publish.topic_name, Topic::from("clients/status"));
+        assert_eq!(publish.message, b"offline".to_vec());
+    }
+
+    #[test]
+    fn into_publish_retain_not_supported() {
+        let will = Will {
+            retain: true,
+            ..Will::with_message(Topic::from("clients/status"), "offline")
+        };
+        assert!(matches!(
+            will.into_publish_checked(false),
+            Err(Error::Reason(RetainNotSupported))
+        ));
+    }
+
+    #[test]
+    fn into_publish_not_retained() {
+        let will = Will::with_message(Topic::from("clients/status"), "offline");
+        assert!(!will.into_publish_checked(false).unwrap().retain);
+    }
 }
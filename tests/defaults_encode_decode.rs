@@ -7,7 +7,11 @@ use std::io::Cursor;
 #[tokio::test]
 async fn default_connect() {
     let mut encoded = Vec::new();
-    let send_packet: Packet = Connect::default().into();
+    let send_packet: Packet = Connect {
+        clean_start: true,
+        ..Default::default()
+    }
+    .into();
     let send_size = send_packet
         .encode(&mut encoded)
         .await
@@ -19,7 +23,13 @@ async fn default_connect() {
         .await
         .expect("Cannot decode Connect");
     if let Packet::Connect(receive_packet) = receive_result {
-        assert_eq!(receive_packet, Connect::default());
+        assert_eq!(
+            receive_packet,
+            Connect {
+                clean_start: true,
+                ..Default::default()
+            }
+        );
     } else {
         panic!("Incorrect packet type");
     }
@@ -29,6 +39,7 @@ async fn default_connect() {
 async fn connect_with_default_auth() {
     let mut encoded = Vec::new();
     let send_packet: Packet = Connect {
+        clean_start: true,
         authentication: Some(Default::default()),
         ..Default::default()
     }
@@ -47,6 +58,7 @@ async fn connect_with_default_auth() {
         assert_eq!(
             receive_packet,
             Connect {
+                clean_start: true,
                 authentication: Some(Default::default()),
                 ..Default::default()
             }
@@ -79,107 +91,62 @@ async fn default_connack() {
 
 #[tokio::test]
 async fn default_publish() {
+    // The default topic name is empty and there is no topic alias
     let mut encoded = Vec::new();
     let send_packet: Packet = Publish::default().into();
-    let send_size = send_packet
-        .encode(&mut encoded)
-        .await
-        .expect("Cannot encode Publish packet");
-    assert!(send_size > 0);
-
-    let mut cursor = Cursor::new(encoded);
-    let receive_result = Packet::decode(&mut cursor)
-        .await
-        .expect("Cannot decode Publish");
-    if let Packet::Publish(receive_packet) = receive_result {
-        assert_eq!(receive_packet, Publish::default());
-    } else {
-        panic!("Incorrect packet type");
-    }
+    let send_result = send_packet.encode(&mut encoded).await;
+    assert!(matches!(
+        send_result,
+        Err(Error::Context(ReasonCode::ProtocolError, _))
+    ));
 }
 
 #[tokio::test]
 async fn default_puback() {
+    // The default packet identifier is 0, which is invalid
     let mut encoded = Vec::new();
     let send_packet: Packet = PubAck::default().into();
-    let send_size = send_packet
-        .encode(&mut encoded)
-        .await
-        .expect("Cannot encode PubAck packet");
-    assert!(send_size > 0);
-
-    let mut cursor = Cursor::new(encoded);
-    let receive_result = Packet::decode(&mut cursor)
-        .await
-        .expect("Cannot decode PubAck");
-    if let Packet::PubAck(receive_packet) = receive_result {
-        assert_eq!(receive_packet, PubAck::default());
-    } else {
-        panic!("Incorrect packet type");
-    }
+    let send_result = send_packet.encode(&mut encoded).await;
+    assert!(matches!(
+        send_result,
+        Err(Error::Reason(ReasonCode::ProtocolError))
+    ));
 }
 
 #[tokio::test]
 async fn default_pubrec() {
+    // The default packet identifier is 0, which is invalid
     let mut encoded = Vec::new();
     let send_packet: Packet = PubRec::default().into();
-    let send_size = send_packet
-        .encode(&mut encoded)
-        .await
-        .expect("Cannot encode PubRec packet");
-    assert!(send_size > 0);
-
-    let mut cursor = Cursor::new(encoded);
-    let receive_result = Packet::decode(&mut cursor)
-        .await
-        .expect("Cannot decode PubRec");
-    if let Packet::PubRec(receive_packet) = receive_result {
-        assert_eq!(receive_packet, PubRec::default());
-    } else {
-        panic!("Incorrect packet type");
-    }
+    let send_result = send_packet.encode(&mut encoded).await;
+    assert!(matches!(
+        send_result,
+        Err(Error::Reason(ReasonCode::ProtocolError))
+    ));
 }
 
 #[tokio::test]
 async fn default_pubrel() {
+    // The default packet identifier is 0, which is invalid
     let mut encoded = Vec::new();
     let send_packet: Packet = PubRel::default().into();
-    let send_size = send_packet
-        .encode(&mut encoded)
-        .await
-        .expect("Cannot encode PubRel packet");
-    assert!(send_size > 0);
-
-    let mut cursor = Cursor::new(encoded);
-    let receive_result = Packet::decode(&mut cursor)
-        .await
-        .expect("Cannot decode PubRel");
-    if let Packet::PubRel(receive_packet) = receive_result {
-        assert_eq!(receive_packet, PubRel::default());
-    } else {
-        panic!("Incorrect packet type");
-    }
+    let send_result = send_packet.encode(&mut encoded).await;
+    assert!(matches!(
+        send_result,
+        Err(Error::Reason(ReasonCode::ProtocolError))
+    ));
 }
 
 #[tokio::test]
 async fn default_pubcomp() {
+    // The default packet identifier is 0, which is invalid
     let mut encoded = Vec::new();
     let send_packet: Packet = PubComp::default().into();
-    let send_size = send_packet
-        .encode(&mut encoded)
-        .await
-        .expect("Cannot encode PubComp packet");
-    assert!(send_size > 0);
-
-    let mut cursor = Cursor::new(encoded);
-    let receive_result = Packet::decode(&mut cursor)
-        .await
-        .expect("Cannot decode PubComp");
-    if let Packet::PubComp(receive_packet) = receive_result {
-        assert_eq!(receive_packet, PubComp::default());
-    } else {
-        panic!("Incorrect packet type");
-    }
+    let send_result = send_packet.encode(&mut encoded).await;
+    assert!(matches!(
+        send_result,
+        Err(Error::Reason(ReasonCode::ProtocolError))
+    ));
 }
 
 #[tokio::test]
@@ -202,39 +169,23 @@ async fn default_subscribe() {
 
 #[tokio::test]
 async fn default_suback() {
+    // The default packet has no reason codes, which is invalid
     let mut encoded = Vec::new();
     let send_packet: Packet = SubAck::default().into();
-    let send_size = send_packet
-        .encode(&mut encoded)
-        .await
-        .expect("Cannot encode SubAck packet");
-    assert!(send_size > 0);
-
-    let mut cursor = Cursor::new(encoded);
-    let receive_result = Packet::decode(&mut cursor)
-        .await
-        .expect("Cannot decode SubAck");
-    if let Packet::SubAck(receive_packet) = receive_result {
-        assert_eq!(receive_packet, SubAck::default());
-    } else {
-        panic!("Incorrect packet type");
-    }
+    let send_result = send_packet.encode(&mut encoded).await;
+    assert!(matches!(
+        send_result,
+        Err(Error::Reason(ReasonCode::ProtocolError))
+    ));
 }
 
 #[tokio::test]
 async fn default_unsubscribe() {
     let mut encoded = Vec::new();
     let send_packet: Packet = UnSubscribe::default().into();
-    let send_size = send_packet
-        .encode(&mut encoded)
-        .await
-        .expect("Cannot encode UnSubscribe packet");
-    assert!(send_size > 0);
-
-    let mut cursor = Cursor::new(encoded);
-    let receive_result = Packet::decode(&mut cursor).await;
+    let send_result = send_packet.encode(&mut encoded).await;
     assert!(matches!(
-        receive_result,
+        send_result,
         Err(Error::Reason(ReasonCode::ProtocolError))
     ));
 }
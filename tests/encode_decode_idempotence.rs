@@ -0,0 +1,170 @@
+use sage_mqtt::{
+    Authentication, BinaryData, ConnAck, Connect, Packet, Publish, QoS, ReasonCode, RetainHandling,
+    SubAck, Subscribe, SubscriptionOptions, Topic, Will,
+};
+
+/// Encodes `sample`, decodes it back and encodes the result again, checking
+/// both encodings are identical. Returns the decoded packet.
+async fn round_trip(sample: Packet) -> Packet {
+    let encoded = sample
+        .encode_to_vec()
+        .await
+        .expect("Cannot encode sample packet");
+    let decoded = Packet::decode(&encoded[..])
+        .await
+        .expect("Cannot decode sample packet");
+    let reencoded = decoded
+        .clone()
+        .encode_to_vec()
+        .await
+        .expect("Cannot encode decoded packet");
+    assert_eq!(reencoded, encoded);
+    decoded
+}
+
+#[tokio::test]
+async fn connect() {
+    let sample = Connect {
+        clean_start: true,
+        user_name: Some("Willow".into()),
+        password: Some(BinaryData::try_new("Jaden").unwrap()),
+        keep_alive: 30,
+        session_expiry_interval: Some(3600),
+        receive_maximum: 100,
+        maximum_packet_size: Some(1024),
+        topic_alias_maximum: 10,
+        request_response_information: true,
+        request_problem_information: false,
+        user_properties: vec![
+            ("Mogwaï".into(), "Cat".into()),
+            ("Mogwaï".into(), "Dog".into()),
+        ],
+        authentication: Some(Authentication {
+            method: "SCRAM-SHA-1".into(),
+            data: BinaryData::try_new(vec![0x0D, 0x15, 0xEA, 0x5E]).unwrap(),
+        }),
+        client_id: Some("Sage451".into()),
+        will: Some(Will {
+            qos: QoS::AtLeastOnce,
+            retain: true,
+            delay_interval: 10,
+            message_expiry_interval: Some(60),
+            content_type: "text/plain".into(),
+            response_topic: Some(Topic::from("clients/responses")),
+            user_properties: vec![("Willow".into(), "Jarod".into())],
+            ..Will::with_message(Topic::from("clients/status"), "offline")
+        }),
+    };
+    match round_trip(sample.clone().into()).await {
+        Packet::Connect(decoded) => assert_eq!(decoded, sample),
+        _ => panic!("Incorrect packet type"),
+    }
+}
+
+#[tokio::test]
+async fn connack() {
+    let sample = ConnAck {
+        session_present: true,
+        session_expiry_interval: Some(3600),
+        receive_maximum: 100,
+        maximum_qos: QoS::AtLeastOnce,
+        retain_available: false,
+        maximum_packet_size: Some(1024),
+        assigned_client_id: Some("Sage451".into()),
+        topic_alias_maximum: 10,
+        reason_string: Some("Black Betty".into()),
+        user_properties: vec![("Mogwaï".into(), "Cat".into())],
+        wildcard_subscription_available: false,
+        subscription_identifiers_available: false,
+        shared_subscription_available: false,
+        keep_alive: Some(30),
+        response_information: Some("responses".into()),
+        reference: Some("mqtt.example.com".into()),
+        ..Default::default()
+    };
+    match round_trip(sample.clone().into()).await {
+        Packet::ConnAck(decoded) => assert_eq!(decoded, sample),
+        _ => panic!("Incorrect packet type"),
+    }
+}
+
+#[tokio::test]
+async fn publish() {
+    let sample = Publish {
+        duplicate: true,
+        qos: QoS::AtLeastOnce,
+        retain: true,
+        packet_identifier: Some(1337),
+        payload_format_indicator: true,
+        message_expiry_interval: Some(60),
+        topic_alias: Some(3),
+        response_topic: Some(Topic::from("sensors/responses")),
+        correlation_data: Some(BinaryData::try_new(vec![0x2A]).unwrap()),
+        user_properties: vec![
+            ("unit".into(), "celsius".into()),
+            ("unit".into(), "kelvin".into()),
+        ],
+        content_type: "text/plain".into(),
+        ..Publish::new("sensors/temperature", "21.5")
+    };
+    match round_trip(sample.clone().into()).await {
+        Packet::Publish(decoded) => assert_eq!(decoded, sample),
+        _ => panic!("Incorrect packet type"),
+    }
+}
+
+#[tokio::test]
+async fn subscribe() {
+    let sample = Subscribe {
+        packet_identifier: 1337,
+        subscription_identifier: Some(42),
+        user_properties: vec![("Mogwaï".into(), "Cat".into())],
+        subscriptions: vec![
+            (
+                Topic::from("sensors/+/temperature"),
+                SubscriptionOptions {
+                    qos: QoS::ExactlyOnce,
+                    no_local: true,
+                    retain_as_published: true,
+                    retain_handling: RetainHandling::DontSend,
+                },
+            ),
+            (Topic::from("sensors/#"), SubscriptionOptions::default()),
+        ],
+    };
+    match round_trip(sample.clone().into()).await {
+        Packet::Subscribe(decoded) => assert_eq!(decoded, sample),
+        _ => panic!("Incorrect packet type"),
+    }
+}
+
+#[tokio::test]
+async fn suback() {
+    let sample = SubAck {
+        packet_identifier: 1337,
+        user_properties: vec![("Mogwaï".into(), "Cat".into())],
+        reason_codes: vec![
+            ReasonCode::GrantedQoS2,
+            ReasonCode::Success,
+            ReasonCode::NotAuthorized,
+        ],
+    };
+    match round_trip(sample.clone().into()).await {
+        Packet::SubAck(decoded) => assert_eq!(decoded, sample),
+        _ => panic!("Incorrect packet type"),
+    }
+}
+
+#[tokio::test]
+async fn publish_empty_message_with_topic_alias() {
+    let sample = Publish {
+        qos: QoS::AtLeastOnce,
+        packet_identifier: Some(1337),
+        topic_alias: Some(3),
+        ..Publish::new(Topic::default(), "")
+    };
+    match round_trip(sample.clone().into()).await {
+        Packet::Publish(decoded) => assert_eq!(decoded, sample),
+        _ => panic!("Incorrect packet type"),
+    }
+}